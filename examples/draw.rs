@@ -45,23 +45,18 @@ fn main() -> anyhow::Result<()> {
         .with_resizable(true)
         .build(&event_loop)?;
 
-    let cx = e2::Context::new(&window, wgpu::Backends::PRIMARY)?;
-    cx.configure_surface(WIDTH, HEIGHT, wgpu::PresentMode::Mailbox);
+    let (cx, mut surface) = e2::Context::new(&window, wgpu::Backends::PRIMARY)?;
+    surface.configure(&cx, WIDTH, HEIGHT, wgpu::PresentMode::Mailbox, true);
+    let surface_format = surface.format().unwrap();
 
     let mesh_pipe = e2::MeshRenderPipeline::new(
         &cx,
         1,
-        cx.surface.get_supported_formats(&cx.adapter)[0],
+        surface_format,
         Some(wgpu::BlendState::ALPHA_BLENDING),
         None,
     );
-    let batch_pipe = e2::BatchRenderPipeline::new(
-        &cx,
-        1,
-        cx.surface.get_supported_formats(&cx.adapter)[0],
-        None,
-        None,
-    );
+    let batch_pipe = e2::BatchRenderPipeline::new(&cx, 1, surface_format, None, None);
 
     let mut mesh = e2::MeshRenderer::new(&cx, &mesh_pipe);
     let mut batch = e2::BatchRenderer::new(&batch_pipe);
@@ -112,7 +107,7 @@ fn main() -> anyhow::Result<()> {
                     return;
                 }
 
-                let swapchain = cx.next_frame().unwrap();
+                let swapchain = surface.next_frame(&cx).unwrap();
                 let view = swapchain.texture.create_view(&Default::default());
 
                 let mut frame = e2::Frame::new(&cx);
@@ -121,10 +116,13 @@ fn main() -> anyhow::Result<()> {
 
                 {
                     let mut pass = e2::SimpleRenderPass {
+                        label: None,
                         target: &view,
                         resolve: None,
                         clear: Some(e2::Color::BLACK),
+                        extra_targets: &[],
                         depth_stencil: None,
+                        format: e2::PassFormat::new(surface_format),
                     }
                     .begin(&mut frame);
 
@@ -160,24 +158,22 @@ fn main() -> anyhow::Result<()> {
             }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::Resized(size) => {
-                    if size.width * size.height > 0 {
-                        cx.configure_surface(size.width, size.height, wgpu::PresentMode::Mailbox);
-                        ortho = glam::Mat4::orthographic_rh(
-                            0.,
-                            size.width as _,
-                            size.height as _,
-                            0.,
-                            0.,
-                            1.,
-                        );
-                        draws.update(
-                            &cx,
-                            &(0..10000)
-                                .map(|_| random_draw(ortho, size.width as _, size.height as _))
-                                .collect::<Vec<_>>(),
-                        );
-                    }
+                WindowEvent::Resized(size) if size.width * size.height > 0 => {
+                    surface.resize(&cx, size.width, size.height);
+                    ortho = glam::Mat4::orthographic_rh(
+                        0.,
+                        size.width as _,
+                        size.height as _,
+                        0.,
+                        0.,
+                        1.,
+                    );
+                    draws.update(
+                        &cx,
+                        &(0..10000)
+                            .map(|_| random_draw(ortho, size.width as _, size.height as _))
+                            .collect::<Vec<_>>(),
+                    );
                 }
                 _ => {}
             },
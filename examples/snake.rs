@@ -216,12 +216,12 @@ fn main() -> anyhow::Result<()> {
         .with_resizable(false)
         .build(&event_loop)?;
 
-    let cx = e2::Context::new(&window, wgpu::Backends::PRIMARY)?;
-    cx.configure_surface(WIDTH, HEIGHT, wgpu::PresentMode::Mailbox);
+    let (cx, mut surface) = e2::Context::new(&window, wgpu::Backends::PRIMARY)?;
+    surface.configure(&cx, WIDTH, HEIGHT, wgpu::PresentMode::Mailbox, true);
 
     let mut game = Game::new();
 
-    let surface_format = cx.surface.get_supported_formats(&cx.adapter)[0];
+    let surface_format = surface.format().unwrap();
     let batch_pipe = e2::BatchRenderPipeline::new(&cx, 1, surface_format, None, None);
 
     let depth = e2::RenderTexture::from_depth(1, WIDTH, HEIGHT, false).create(&cx);
@@ -252,17 +252,20 @@ fn main() -> anyhow::Result<()> {
 
         match event {
             Event::RedrawRequested(_) => {
-                let swapchain = cx.next_frame().unwrap();
+                let swapchain = surface.next_frame(&cx).unwrap();
                 let view = swapchain.texture.create_view(&Default::default());
 
                 let mut frame = e2::Frame::new(&cx);
 
                 {
                     let mut pass = e2::SimpleRenderPass {
+                        label: None,
                         target: &view,
                         resolve: None,
                         clear: Some(e2::Color::BLACK),
+                        extra_targets: &[],
                         depth_stencil: None,
+                        format: e2::PassFormat::new(surface_format),
                     }
                     .begin(&mut frame);
 
@@ -318,10 +321,10 @@ fn main() -> anyhow::Result<()> {
             }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::KeyboardInput { input, .. } => {
-                    if input.state == ElementState::Pressed {
-                        game.input(input.virtual_keycode.unwrap());
-                    }
+                WindowEvent::KeyboardInput { input, .. }
+                    if input.state == ElementState::Pressed =>
+                {
+                    game.input(input.virtual_keycode.unwrap());
                 }
                 _ => {}
             },
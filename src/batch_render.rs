@@ -5,6 +5,7 @@ use std::sync::Arc;
 #[derive(Debug, Clone)]
 struct InstanceBuffer {
     pub buffer: Arc<wgpu::Buffer>,
+    pub id: u64,
     pub size: u64,
     pub free: bool,
 }
@@ -30,6 +31,9 @@ pub struct BatchRenderer {
     storage_slot: u32,
     texture_slot: u32,
     sampler_slot: u32,
+
+    /// Blend mode and pipeline last bound by `set_blend`.
+    bound: Option<(BlendMode, Arc<wgpu::RenderPipeline>)>,
 }
 
 impl BatchRenderer {
@@ -39,7 +43,7 @@ impl BatchRenderer {
     /// The pipeline handle only acts a reference pipeline layout.
     pub fn new(pipeline: &BatchRenderPipeline) -> Self {
         let instance_desc = wgpu::BufferDescriptor {
-            label: None,
+            label: Some("e2 batch instances"),
             size: 0,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
@@ -57,13 +61,15 @@ impl BatchRenderer {
             texture_layout,
             sampler_layout,
 
-            storage_binds: BindCache::new(),
-            texture_binds: BindCache::new(),
-            sampler_binds: BindCache::new(),
+            storage_binds: BindCache::with_label("e2 batch instances bind group"),
+            texture_binds: BindCache::with_label("e2 batch texture bind group"),
+            sampler_binds: BindCache::with_label("e2 batch sampler bind group"),
 
             storage_slot: 0,
             texture_slot: 1,
             sampler_slot: 2,
+
+            bound: None,
         }
     }
 
@@ -77,23 +83,11 @@ impl BatchRenderer {
     }
 
     /// Binds a sampler for use with the proceeding draw calls.
-    pub fn bind_sampler<'a>(
-        &mut self,
-        cx: &Context,
-        pass: &'a mut wgpu::RenderPass,
-        sampler: &Sampler,
-    ) {
-        let group = self.sampler_binds.get(
+    pub fn bind_sampler(&mut self, cx: &Context, pass: &mut wgpu::RenderPass, sampler: &Sampler) {
+        let group = BindGroup(&[Resource::Sampler(sampler)]).get(
             cx,
-            sampler.id(),
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.sampler_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&sampler.sampler),
-                }],
-            },
+            &mut self.sampler_binds,
+            &self.sampler_layout,
         );
 
         pass.set_bind_group(
@@ -103,6 +97,33 @@ impl BatchRenderer {
         );
     }
 
+    /// Switches `pass` to the built-in pipeline using `blend`, for the proceeding draw calls.
+    ///
+    /// The pipeline matches [ArenaRenderPass::format].
+    /// Does nothing if the pipeline using `blend` is still bound to `pass`.
+    pub fn set_blend(&mut self, cx: &Context, pass: &mut ArenaRenderPass, blend: BlendMode) {
+        let bound = self.bound.as_ref().is_some_and(|(current, pipeline)| {
+            *current == blend && pass.is_pipeline_bound(pipeline)
+        });
+        if !bound {
+            let pipeline = cx.pipelines.get(
+                cx,
+                BuiltinPipeline::Batch,
+                &PipelineKey::new(&pass.format, blend),
+            );
+            pass.set_pipeline(pipeline.clone());
+            self.bound = Some((blend, pipeline));
+        }
+    }
+
+    /// Binds the built-in pipeline matching [ArenaRenderPass::format] to `pass`, using [BlendMode::Alpha].
+    ///
+    /// This replaces [BatchRenderPipeline::bind] when rendering into passes of varying formats.
+    pub fn bind_pass(&mut self, cx: &Context, pass: &mut ArenaRenderPass) {
+        Slot3BatchRenderer::bind(self, 0, 1, 2);
+        self.set_blend(cx, pass, BlendMode::default());
+    }
+
     /// Draws a specified mesh and texture multiple times.
     ///
     /// The draw is instanced `draws.len()` times, and each draw uses the corresponding `Draw`.
@@ -117,26 +138,34 @@ impl BatchRenderer {
         draws: &[BatchDraw],
     ) {
         let size = GpuDraw::std430_size_static() as u64 * draws.len() as u64;
-        let (index, buf) = if let Some((i, buf)) = self
+        let (id, buf) = if let Some(buf) = self
             .instances
             .iter_mut()
-            .enumerate()
-            .filter(|(_, x)| x.free && x.size >= size)
-            .min_by(|(_, x), (_, y)| x.size.cmp(&y.size))
+            .filter(|x| x.free && x.size >= size)
+            .min_by_key(|x| x.size)
         {
             buf.free = false;
-            (i, buf.buffer.clone())
+            (buf.id, buf.buffer.clone())
         } else {
+            cx.stats.buffer_allocated();
+            let label = format!(
+                "{} #{}",
+                self.instance_desc.label.unwrap_or_default(),
+                self.instances.len()
+            );
             let buffer = Arc::new(cx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&label),
                 size,
                 ..self.instance_desc
             }));
+            let id = next_buffer_id();
             self.instances.push(InstanceBuffer {
                 buffer: buffer.clone(),
+                id,
                 size,
                 free: false,
             });
-            (self.instances.len() - 1, buffer)
+            (id, buffer)
         };
 
         let draws = draws
@@ -146,36 +175,21 @@ impl BatchRenderer {
         cx.queue.write_buffer(buf.as_ref(), 0, unsafe {
             std::slice::from_raw_parts(draws.as_ptr() as *const u8, size as _)
         });
-
-        let storage_group = self.storage_binds.get(
-            cx,
-            index as _,
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.storage_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: buf.as_ref(),
-                        offset: 0,
-                        size: None,
-                    }),
-                }],
+        cx.stats.uploaded(size);
+
+        let storage_group = BindGroup(&[Resource::Buffer(
+            id,
+            wgpu::BufferBinding {
+                buffer: buf.as_ref(),
+                offset: 0,
+                size: None,
             },
-        );
+        )])
+        .get(cx, &mut self.storage_binds, &self.storage_layout);
 
-        let texture_group = self.texture_binds.get(
-            cx,
-            texture.id(),
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.texture_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture.view),
-                }],
-            },
-        );
+        let texture_group = BindGroup(&[Resource::Texture(texture)])
+            .debug_check(&[BUILTIN_TEXTURE_ENTRY])
+            .get(cx, &mut self.texture_binds, &self.texture_layout);
 
         pass.set_bind_group(self.storage_slot, storage_group, &[]);
         pass.set_bind_group(self.texture_slot, texture_group, &[]);
@@ -184,6 +198,7 @@ impl BatchRenderer {
         pass.set_index_buffer(mesh.indices.clone(), 0, wgpu::IndexFormat::Uint32);
 
         pass.draw_indexed(0..mesh.index_count as u32, 0, 0..draws.len() as u32);
+        cx.stats.draw(draws.len() as _);
     }
 
     /// Functions identically to [BatchRenderer::draw], except the
@@ -198,35 +213,19 @@ impl BatchRenderer {
         texture: &Texture,
         array: &DrawArray<BatchDraw>,
     ) {
-        let storage_group = self.storage_binds.get(
-            cx,
+        let storage_group = BindGroup(&[Resource::Buffer(
             array.id(),
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.storage_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: array.buffer(),
-                        offset: 0,
-                        size: None,
-                    }),
-                }],
+            wgpu::BufferBinding {
+                buffer: array.buffer(),
+                offset: 0,
+                size: None,
             },
-        );
+        )])
+        .get(cx, &mut self.storage_binds, &self.storage_layout);
 
-        let texture_group = self.texture_binds.get(
-            cx,
-            texture.id(),
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.texture_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture.view),
-                }],
-            },
-        );
+        let texture_group = BindGroup(&[Resource::Texture(texture)])
+            .debug_check(&[BUILTIN_TEXTURE_ENTRY])
+            .get(cx, &mut self.texture_binds, &self.texture_layout);
 
         pass.set_bind_group(self.storage_slot, storage_group, &[]);
         pass.set_bind_group(self.texture_slot, texture_group, &[]);
@@ -235,6 +234,7 @@ impl BatchRenderer {
         pass.set_index_buffer(mesh.indices.clone(), 0, wgpu::IndexFormat::Uint32);
 
         pass.draw_indexed(0..mesh.index_count as u32, 0, 0..array.len() as u32);
+        cx.stats.draw(array.len() as _);
     }
 }
 
@@ -254,6 +254,8 @@ impl Slot3BatchRenderer for BatchRenderer {
         self.storage_slot = storage;
         self.texture_slot = texture;
         self.sampler_slot = sampler;
+        // the pass now uses whichever pipeline is being bound
+        self.bound = None;
     }
 }
 
@@ -262,10 +264,13 @@ impl Slot3BatchRenderer for BatchRenderer {
 pub struct BatchRenderPipeline {
     pub layout: Arc<wgpu::PipelineLayout>,
     pub pipeline: Arc<wgpu::RenderPipeline>,
+    format: PassFormat,
 }
 
 impl BatchRenderPipeline {
     /// Creates a new [BatchRenderPipeline] with the given parameters.
+    ///
+    /// The shader and pipeline layout are shared through [Context::pipelines], so this is cheap to call repeatedly.
     pub fn new(
         cx: &Context,
         samples: u32,
@@ -273,56 +278,46 @@ impl BatchRenderPipeline {
         blend: Option<wgpu::BlendState>,
         depth_stencil: Option<wgpu::DepthStencilState>,
     ) -> Self {
-        let (layout, _) = PipelineLayout(&[
-            BindGroupLayout(&[LayoutEntry::StorageBuffer {
-                visible: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                count: None,
-                dynamic_offset: false,
-                min_binding_size: None,
-                read_only: true,
-            }]),
-            BindGroupLayout(&[LayoutEntry::Texture {
-                visible: wgpu::ShaderStages::FRAGMENT,
-                count: None,
-                ty: wgpu::TextureSampleType::Float { filterable: true },
-                dimension: wgpu::TextureViewDimension::D2,
-                multisampled: false,
-            }]),
-            BindGroupLayout(&[LayoutEntry::Sampler {
-                visible: wgpu::ShaderStages::FRAGMENT,
-                count: None,
-                comparison: false,
-            }]),
-        ])
-        .create(cx);
-
-        let shader = cx
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(include_str!("shader/batch.wgsl").into()),
-            });
-
-        let pipeline = SimpleRenderPipeline {
-            layout: Some(&layout),
-            vertex: &shader,
-            fragment: &shader,
-            vertex_entry: "vs_main",
-            fragment_entry: "fs_main",
-            vertex_layout: Vertex::layout(),
-            samples,
+        let pipeline = cx.pipelines.create(
+            cx,
+            BuiltinPipeline::Batch,
             format,
+            samples,
             blend,
-            depth_stencil,
-        }
-        .create(cx);
+            depth_stencil.clone(),
+            wgpu::PrimitiveTopology::TriangleList,
+            "fs_main",
+        );
 
         BatchRenderPipeline {
-            layout: Arc::new(layout),
+            layout: cx.pipelines.layout(cx, BuiltinPipeline::Batch),
             pipeline: Arc::new(pipeline),
+            format: PassFormat {
+                format,
+                samples,
+                depth_stencil,
+            },
         }
     }
 
+    /// Returns a variant of this pipeline using `blend`, creating it if needed.
+    ///
+    /// Variants share the pipeline layout, so bind groups remain valid when switching between them.
+    /// They are memoized in [Context::pipelines].
+    pub fn variant(&self, cx: &Context, blend: BlendMode) -> Arc<wgpu::RenderPipeline> {
+        cx.pipelines.get(
+            cx,
+            BuiltinPipeline::Batch,
+            &PipelineKey::new(&self.format, blend),
+        )
+    }
+
+    /// Returns the attachment configuration this pipeline renders into.
+    #[inline]
+    pub fn format(&self) -> &PassFormat {
+        &self.format
+    }
+
     /// Bind the pipeline and renderer to a given render pass.
     pub fn bind(&self, pass: &mut ArenaRenderPass, renderer: &mut impl Slot3BatchRenderer) {
         pass.set_pipeline(self.pipeline.clone());
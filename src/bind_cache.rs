@@ -5,6 +5,7 @@ use std::{collections::HashMap, sync::Arc};
 #[derive(Debug, Clone)]
 pub struct BindCache {
     cache: HashMap<u64, Arc<wgpu::BindGroup>>,
+    label: Option<&'static str>,
 }
 
 impl BindCache {
@@ -12,6 +13,16 @@ impl BindCache {
     pub fn new() -> Self {
         BindCache {
             cache: HashMap::new(),
+            label: None,
+        }
+    }
+
+    /// Creates a new [BindCache] which labels the bind groups it creates as `"{label} #{key}"`,
+    /// unless the descriptor passed to [BindCache::get] already has a label.
+    pub fn with_label(label: &'static str) -> Self {
+        BindCache {
+            cache: HashMap::new(),
+            label: Some(label),
         }
     }
 
@@ -23,9 +34,28 @@ impl BindCache {
         key: u64,
         or_insert: &wgpu::BindGroupDescriptor,
     ) -> Arc<wgpu::BindGroup> {
+        let label = self.label;
         self.cache
             .entry(key)
-            .or_insert_with(|| Arc::new(cx.device.create_bind_group(or_insert)))
+            .or_insert_with(|| {
+                cx.stats.bind_group_created();
+                let label = label.map(|label| format!("{} #{}", label, key));
+                Arc::new(cx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: or_insert.label.or(label.as_deref()),
+                    ..*or_insert
+                }))
+            })
             .clone()
     }
+
+    /// Drops all the cached bind groups, along with the resources they keep alive.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+impl Default for BindCache {
+    fn default() -> Self {
+        Self::new()
+    }
 }
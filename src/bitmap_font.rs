@@ -0,0 +1,656 @@
+use crate::*;
+use std::{collections::HashMap, path::Path};
+
+/// Metrics of a single glyph in a [BitmapFont].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitmapGlyph {
+    /// Index of the page texture containing the glyph.
+    pub page: usize,
+    /// Pixel rectangle of the glyph within the page texture.
+    pub src_rect: Rect,
+    /// Offset from the cursor to the top-left of the glyph.
+    pub offset: glam::Vec2,
+    /// How far to advance the cursor after drawing the glyph.
+    pub advance: f32,
+}
+
+/// Parsed AngelCode BMFont (`.fnt`) description, in either the text or XML format.
+///
+/// This does not include the page textures themselves; see [BitmapFont].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitmapFontData {
+    /// Name of the font face.
+    pub face: String,
+    /// Distance in pixels between each line of text.
+    pub line_height: f32,
+    /// Distance in pixels from the top of a line to the baseline.
+    pub base: f32,
+    /// Width of the page textures.
+    pub scale_w: f32,
+    /// Height of the page textures.
+    pub scale_h: f32,
+    /// Page texture file names, relative to the `.fnt` file, indexed by page ID.
+    pub pages: Vec<String>,
+    pub glyphs: HashMap<char, BitmapGlyph>,
+    /// Kerning amounts keyed by `(first, second)` character pairs.
+    pub kerning: HashMap<(char, char), f32>,
+}
+
+impl BitmapFontData {
+    /// Parses a BMFont description from `source`.
+    ///
+    /// Both the text and XML formats are supported; the binary format is not.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut data = BitmapFontData {
+            face: String::new(),
+            line_height: 0.,
+            base: 0.,
+            scale_w: 1.,
+            scale_h: 1.,
+            pages: Vec::new(),
+            glyphs: HashMap::new(),
+            kerning: HashMap::new(),
+        };
+
+        let tags: Vec<&str> = if source.trim_start().starts_with('<') {
+            source
+                .split('<')
+                .map(|tag| tag.split('>').next().unwrap_or(""))
+                .filter(|tag| !tag.starts_with('?') && !tag.starts_with('!'))
+                .map(|tag| tag.trim_end_matches('/'))
+                .collect()
+        } else {
+            source.lines().collect()
+        };
+
+        for tag in tags {
+            let (name, attrs) = parse_tag(tag);
+            let attr = |key: &str| -> Result<f32> {
+                attrs
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .and_then(|(_, v)| v.parse().ok())
+                    .ok_or_else(|| {
+                        Error::InvalidBitmapFont(format!(
+                            "missing or invalid `{}` in `{}`",
+                            key, name
+                        ))
+                    })
+            };
+            let character = |key: &str| -> Result<char> {
+                let id = attr(key)? as u32;
+                char::from_u32(id)
+                    .ok_or_else(|| Error::InvalidBitmapFont(format!("invalid character id {}", id)))
+            };
+
+            match name {
+                "info" => {
+                    data.face = attrs
+                        .iter()
+                        .find(|(k, _)| *k == "face")
+                        .map(|(_, v)| v.to_string())
+                        .unwrap_or_default();
+                }
+                "common" => {
+                    data.line_height = attr("lineHeight")?;
+                    data.base = attr("base")?;
+                    data.scale_w = attr("scaleW")?;
+                    data.scale_h = attr("scaleH")?;
+                }
+                "page" => {
+                    let id = attr("id")? as usize;
+                    let file = attrs
+                        .iter()
+                        .find(|(k, _)| *k == "file")
+                        .map(|(_, v)| v.to_string())
+                        .ok_or_else(|| Error::InvalidBitmapFont("page without file".into()))?;
+                    if data.pages.len() <= id {
+                        data.pages.resize(id + 1, String::new());
+                    }
+                    data.pages[id] = file;
+                }
+                "char" => {
+                    data.glyphs.insert(
+                        character("id")?,
+                        BitmapGlyph {
+                            page: attr("page")? as usize,
+                            src_rect: Rect::new(
+                                attr("x")?,
+                                attr("y")?,
+                                attr("width")?,
+                                attr("height")?,
+                            ),
+                            offset: glam::vec2(attr("xoffset")?, attr("yoffset")?),
+                            advance: attr("xadvance")?,
+                        },
+                    );
+                }
+                "kerning" => {
+                    data.kerning
+                        .insert((character("first")?, character("second")?), attr("amount")?);
+                }
+                _ => {}
+            }
+        }
+
+        if data.line_height <= 0. {
+            return Err(Error::InvalidBitmapFont("missing `common` block".into()));
+        }
+
+        if let Some(glyph) = data.glyphs.values().find(|g| g.page >= data.pages.len()) {
+            return Err(Error::InvalidBitmapFont(format!(
+                "glyph references missing page {}",
+                glyph.page
+            )));
+        }
+
+        Ok(data)
+    }
+}
+
+/// Splits a BMFont line or XML tag into its name and `key=value` attributes.
+fn parse_tag(tag: &str) -> (&str, Vec<(&str, &str)>) {
+    let tag = tag.trim();
+    let (name, mut rest) = tag.split_at(tag.find(char::is_whitespace).unwrap_or(tag.len()));
+
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let eq = match rest.find('=') {
+            Some(eq) => eq,
+            None => break,
+        };
+        let key = rest[..eq].trim();
+        rest = &rest[eq + 1..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            rest = quoted.get(end + 1..).unwrap_or("");
+            &quoted[..end]
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+        attrs.push((key, value));
+    }
+
+    (name, attrs)
+}
+
+/// AngelCode BMFont bitmap font with its page textures loaded.
+///
+/// Render with [BitmapTextRenderer].
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    pub data: BitmapFontData,
+    /// Page textures, indexed by page ID.
+    pub pages: Vec<Texture>,
+}
+
+impl BitmapFont {
+    /// Loads a `.fnt` file and its page images from `path`.
+    ///
+    /// Page images are resolved relative to the directory containing the `.fnt` file.
+    pub fn from_path(path: impl AsRef<Path>, cx: &Context, srgb: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let data = BitmapFontData::parse(&std::fs::read_to_string(path)?)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let pages = data
+            .pages
+            .iter()
+            .map(|page| {
+                Ok(ImageTexture {
+                    label: Some(page),
+                    ..ImageTexture::from_path(dir.join(page), srgb)?
+                }
+                .create(cx))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(data, pages)
+    }
+
+    /// Creates a new [BitmapFont] from parsed font data and existing page textures.
+    ///
+    /// Returns an error unless there is exactly one texture per page of `data`.
+    pub fn new(data: BitmapFontData, pages: Vec<Texture>) -> Result<Self> {
+        if pages.len() != data.pages.len() {
+            return Err(Error::InvalidBitmapFont(format!(
+                "font has {} pages, but {} textures were given",
+                data.pages.len(),
+                pages.len()
+            )));
+        }
+        Ok(BitmapFont { data, pages })
+    }
+
+    /// Returns `true` if the font has a glyph for `c`.
+    #[inline]
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.data.glyphs.contains_key(&c)
+    }
+
+    /// Returns the kerning adjustment between `first` and `second`, in unscaled pixels.
+    #[inline]
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.data
+            .kerning
+            .get(&(first, second))
+            .copied()
+            .unwrap_or(0.)
+    }
+
+    /// Lays out `draw` into sprite draws, each paired with the page index of the texture to draw with.
+    ///
+    /// Characters without a glyph fall back to `?` if available, otherwise they are skipped.
+    pub fn layout(&self, draw: &BitmapTextDraw) -> Vec<(usize, SpriteBatchTexturedDraw)> {
+        let scale = draw.scale / self.data.line_height;
+        let line_height = self.data.line_height * scale;
+        let max_width = draw.bounds.map(|b| b.x).unwrap_or(f32::INFINITY);
+        let max_height = draw.bounds.map(|b| b.y).unwrap_or(f32::INFINITY);
+
+        let mut out = Vec::new();
+        let mut y = 0.;
+
+        for line in draw.text.split('\n') {
+            let mut x = 0.;
+            let mut prev = None;
+
+            for word in split_words(line) {
+                let width = self.measure_line(word, scale);
+                if x > 0. && x + width > max_width {
+                    x = 0.;
+                    y += line_height;
+                    prev = None;
+                    if word.starts_with(char::is_whitespace) {
+                        continue;
+                    }
+                }
+
+                for c in word.chars() {
+                    let (c, glyph) = match self.glyph(c) {
+                        Some(glyph) => glyph,
+                        None => continue,
+                    };
+
+                    if let Some(prev) = prev {
+                        x += self.kerning(prev, c) * scale;
+                    }
+
+                    if x > 0. && x + glyph.advance * scale > max_width && !c.is_whitespace() {
+                        x = 0.;
+                        y += line_height;
+                    }
+
+                    if y + line_height > max_height {
+                        return out;
+                    }
+
+                    if glyph.src_rect.size.x > 0. && glyph.src_rect.size.y > 0. {
+                        out.push((
+                            glyph.page,
+                            SpriteBatchTexturedDraw {
+                                color: draw.color,
+                                src_rect: Rect::new(
+                                    glyph.src_rect.origin.x / self.data.scale_w,
+                                    glyph.src_rect.origin.y / self.data.scale_h,
+                                    glyph.src_rect.size.x / self.data.scale_w,
+                                    glyph.src_rect.size.y / self.data.scale_h,
+                                ),
+                                rect: Rect::new(
+                                    draw.origin.x + x + glyph.offset.x * scale,
+                                    draw.origin.y + y + glyph.offset.y * scale,
+                                    glyph.src_rect.size.x * scale,
+                                    glyph.src_rect.size.y * scale,
+                                ),
+                                rotation: 0.,
+                            },
+                        ));
+                    }
+
+                    x += glyph.advance * scale;
+                    prev = Some(c);
+                }
+            }
+
+            y += line_height;
+        }
+
+        out
+    }
+
+    /// Measures the size of `text` when rendered at pixel `scale` without layout bounds.
+    pub fn measure(&self, text: &str, scale: f32) -> glam::Vec2 {
+        let line_scale = scale / self.data.line_height;
+        let lines = text.split('\n');
+        let height = lines.clone().count() as f32 * scale;
+        let width = lines
+            .map(|line| self.measure_line(line, line_scale))
+            .fold(0., f32::max);
+        glam::vec2(width, height)
+    }
+
+    /// Returns the glyph drawn for `c`, along with the character it belongs to (`c` or the `?` fallback).
+    fn glyph(&self, c: char) -> Option<(char, &BitmapGlyph)> {
+        self.data
+            .glyphs
+            .get_key_value(&c)
+            .or_else(|| self.data.glyphs.get_key_value(&'?'))
+            .map(|(&c, glyph)| (c, glyph))
+    }
+
+    fn measure_line(&self, line: &str, scale: f32) -> f32 {
+        let mut width = 0.;
+        let mut prev = None;
+        for c in line.chars() {
+            if let Some((c, glyph)) = self.glyph(c) {
+                if let Some(prev) = prev {
+                    width += self.kerning(prev, c) * scale;
+                }
+                width += glyph.advance * scale;
+                prev = Some(c);
+            }
+        }
+        width
+    }
+}
+
+/// Splits `line` into alternating runs of whitespace and non-whitespace.
+fn split_words(line: &str) -> impl Iterator<Item = &str> {
+    let mut rest = line;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let end = rest
+            .find(|c: char| c.is_whitespace() != first.is_whitespace())
+            .unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(end);
+        rest = tail;
+        Some(word)
+    })
+}
+
+/// Text rendering helper type which renders text using a [BitmapFont] through a [SpriteBatchRenderer].
+pub struct BitmapTextRenderer {
+    pages: Vec<Vec<SpriteBatchTexturedDraw>>,
+}
+
+impl BitmapTextRenderer {
+    /// Create a new [BitmapTextRenderer].
+    pub fn new() -> Self {
+        BitmapTextRenderer { pages: Vec::new() }
+    }
+
+    /// Draws text with `font`, issuing one batched draw per font page.
+    ///
+    /// Transformation is controlled by [SpriteBatchRenderer::set_matrix].
+    pub fn draw(
+        &mut self,
+        cx: &Context,
+        pass: &mut ArenaRenderPass,
+        renderer: &mut SpriteBatchRenderer,
+        font: &BitmapFont,
+        draws: &[BitmapTextDraw],
+    ) {
+        self.pages.resize_with(font.data.pages.len(), Vec::new);
+        for page in &mut self.pages {
+            page.clear();
+        }
+
+        for draw in draws {
+            for (page, sprite) in font.layout(draw) {
+                self.pages[page].push(sprite);
+            }
+        }
+
+        for (page, sprites) in self.pages.iter().enumerate() {
+            if !sprites.is_empty() {
+                renderer.draw(cx, pass, (&font.pages[page], &sprites[..]));
+            }
+        }
+    }
+}
+
+impl Default for BitmapTextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draw data for rendering text with a [BitmapFont].
+pub struct BitmapTextDraw<'a> {
+    /// Top-left of the text.
+    pub origin: glam::Vec2,
+    /// Layout bounds of the text.
+    ///
+    /// `None` means unbounded and thus will render as a single line.
+    pub bounds: Option<glam::Vec2>,
+    /// The text to be rendered.
+    pub text: &'a str,
+    /// Pixel scale (line height) of the text.
+    ///
+    /// Use [BitmapFontData::line_height] to render at the native size of the font.
+    pub scale: f32,
+    /// Color of the text.
+    pub color: Color,
+}
+
+impl<'a> BitmapTextDraw<'a> {
+    /// Constructor for [BitmapTextDraw] without any layout bounds (single line).
+    pub fn unbounded(origin: glam::Vec2, text: &'a str, scale: f32, color: Color) -> Self {
+        BitmapTextDraw {
+            origin,
+            bounds: None,
+            text,
+            scale,
+            color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r#"info face="Test Font" size=32
+common lineHeight=32 base=26 scaleW=256 scaleH=128 pages=2
+page id=0 file="test_0.png"
+page id=1 file="test_1.png"
+chars count=2
+char id=65 x=1 y=2 width=10 height=20 xoffset=1 yoffset=3 xadvance=12 page=0 chnl=15
+char id=86 x=11 y=2 width=10 height=20 xoffset=0 yoffset=3 xadvance=11 page=1 chnl=15
+kernings count=1
+kerning first=65 second=86 amount=-2
+"#;
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<font>
+  <info face="Test Font" size="32"/>
+  <common lineHeight="32" base="26" scaleW="256" scaleH="128" pages="2"/>
+  <pages>
+    <page id="0" file="test_0.png"/>
+    <page id="1" file="test_1.png"/>
+  </pages>
+  <chars count="2">
+    <char id="65" x="1" y="2" width="10" height="20" xoffset="1" yoffset="3" xadvance="12" page="0" chnl="15"/>
+    <char id="86" x="11" y="2" width="10" height="20" xoffset="0" yoffset="3" xadvance="11" page="1" chnl="15"/>
+  </chars>
+  <kernings count="1">
+    <kerning first="65" second="86" amount="-2"/>
+  </kernings>
+</font>
+"#;
+
+    #[test]
+    fn parse_text() {
+        let data = BitmapFontData::parse(TEXT).unwrap();
+        assert_eq!(data.face, "Test Font");
+        assert_eq!(data.line_height, 32.);
+        assert_eq!(data.base, 26.);
+        assert_eq!((data.scale_w, data.scale_h), (256., 128.));
+        assert_eq!(data.pages, ["test_0.png", "test_1.png"]);
+        assert_eq!(
+            data.glyphs[&'A'],
+            BitmapGlyph {
+                page: 0,
+                src_rect: Rect::new(1., 2., 10., 20.),
+                offset: glam::vec2(1., 3.),
+                advance: 12.,
+            }
+        );
+        assert_eq!(data.glyphs[&'V'].page, 1);
+        assert_eq!(data.kerning[&('A', 'V')], -2.);
+    }
+
+    #[test]
+    fn parse_xml_matches_text() {
+        assert_eq!(
+            BitmapFontData::parse(XML).unwrap(),
+            BitmapFontData::parse(TEXT).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_missing_common() {
+        assert!(matches!(
+            BitmapFontData::parse("info face=x\n"),
+            Err(Error::InvalidBitmapFont(_))
+        ));
+    }
+
+    #[test]
+    fn parse_missing_page() {
+        let source = TEXT.replace("page id=1 file=\"test_1.png\"\n", "");
+        assert!(matches!(
+            BitmapFontData::parse(&source),
+            Err(Error::InvalidBitmapFont(_))
+        ));
+    }
+
+    #[test]
+    fn parse_invalid_attribute() {
+        let source = TEXT.replace("xadvance=12", "xadvance=twelve");
+        assert!(matches!(
+            BitmapFontData::parse(&source),
+            Err(Error::InvalidBitmapFont(_))
+        ));
+    }
+
+    #[test]
+    fn new_page_count() {
+        let data = BitmapFontData::parse(TEXT).unwrap();
+        assert!(matches!(
+            BitmapFont::new(data, Vec::new()),
+            Err(Error::InvalidBitmapFont(_))
+        ));
+    }
+
+    fn font() -> BitmapFont {
+        let source = TEXT.replace(
+            "chars count=2\n",
+            "chars count=3\nchar id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=8 page=0 chnl=15\n",
+        );
+        // layout only uses the font data, so the page textures can be left out
+        BitmapFont {
+            data: BitmapFontData::parse(&source).unwrap(),
+            pages: Vec::new(),
+        }
+    }
+
+    fn layout(font: &BitmapFont, text: &str, bounds: Option<glam::Vec2>) -> Vec<(usize, Rect)> {
+        font.layout(&BitmapTextDraw {
+            origin: glam::vec2(100., 200.),
+            bounds,
+            text,
+            scale: 32.,
+            color: Color::WHITE,
+        })
+        .into_iter()
+        .map(|(page, draw)| {
+            (
+                page,
+                Rect::new(
+                    draw.rect.origin.x - 100.,
+                    draw.rect.origin.y - 200.,
+                    draw.rect.size.x,
+                    draw.rect.size.y,
+                ),
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn layout_kerning() {
+        let font = font();
+        assert_eq!(
+            layout(&font, "AV", None),
+            [
+                (0, Rect::new(1., 3., 10., 20.)),
+                // 12 advance, -2 kerning
+                (1, Rect::new(10., 3., 10., 20.)),
+            ]
+        );
+        assert_eq!(font.measure("AV", 32.), glam::vec2(21., 32.));
+        assert_eq!(font.measure("VA", 64.), glam::vec2(46., 64.));
+
+        let draws = font.layout(&BitmapTextDraw::unbounded(
+            glam::Vec2::ZERO,
+            "A",
+            32.,
+            Color::RED,
+        ));
+        assert_eq!(
+            draws[0].1.src_rect,
+            Rect::new(1. / 256., 2. / 128., 10. / 256., 20. / 128.)
+        );
+        assert_eq!(draws[0].1.color, Color::RED);
+    }
+
+    #[test]
+    fn layout_line_breaks() {
+        let font = font();
+        assert_eq!(
+            layout(&font, "A\nV", None),
+            [
+                (0, Rect::new(1., 3., 10., 20.)),
+                (1, Rect::new(0., 35., 10., 20.))
+            ]
+        );
+        assert_eq!(font.measure("A V\nA", 32.), glam::vec2(31., 64.));
+
+        // "AV AV" is 50 pixels wide, so the second word wraps and keeps its kerning
+        assert_eq!(
+            layout(&font, "AV AV", Some(glam::vec2(30., 100.))),
+            [
+                (0, Rect::new(1., 3., 10., 20.)),
+                (1, Rect::new(10., 3., 10., 20.)),
+                (0, Rect::new(1., 35., 10., 20.)),
+                (1, Rect::new(10., 35., 10., 20.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_bounds() {
+        let font = font();
+        // the second line does not fit in the height
+        assert_eq!(
+            layout(&font, "A\nA", Some(glam::vec2(100., 40.))),
+            [(0, Rect::new(1., 3., 10., 20.))]
+        );
+        // words wider than the bounds are broken between characters
+        assert_eq!(
+            layout(&font, "AAA", Some(glam::vec2(30., 100.))),
+            [
+                (0, Rect::new(1., 3., 10., 20.)),
+                (0, Rect::new(13., 3., 10., 20.)),
+                (0, Rect::new(1., 35., 10., 20.)),
+            ]
+        );
+        // characters without a glyph or `?` fallback are skipped
+        assert_eq!(
+            layout(&font, "ZA", None),
+            [(0, Rect::new(1., 3., 10., 20.))]
+        );
+    }
+}
@@ -0,0 +1,72 @@
+/// Common blending presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Standard "over" blending of straight (non-premultiplied) alpha.
+    #[default]
+    Alpha,
+    /// "Over" blending of premultiplied alpha.
+    ///
+    /// Built-in renderers expect premultiplied textures in this mode, but straight alpha draw colors.
+    PremultipliedAlpha,
+    /// Adds the source, weighted by its alpha, to the destination.
+    Additive,
+    /// Multiplies the destination by the source.
+    Multiply,
+    /// Inverse of multiply; brightens the destination by the source.
+    Screen,
+    /// Subtracts the source, weighted by its alpha, from the destination.
+    Subtract,
+    /// Replaces the destination.
+    Opaque,
+}
+
+impl BlendMode {
+    /// Returns the equivalent [wgpu::BlendState], or `None` for [BlendMode::Opaque].
+    pub fn state(self) -> Option<wgpu::BlendState> {
+        let keep_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let color = |src_factor, dst_factor, operation| wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor,
+                dst_factor,
+                operation,
+            },
+            alpha: keep_alpha,
+        };
+
+        match self {
+            BlendMode::Alpha => Some(wgpu::BlendState::ALPHA_BLENDING),
+            BlendMode::PremultipliedAlpha => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            BlendMode::Additive => Some(color(
+                wgpu::BlendFactor::SrcAlpha,
+                wgpu::BlendFactor::One,
+                wgpu::BlendOperation::Add,
+            )),
+            BlendMode::Multiply => Some(color(
+                wgpu::BlendFactor::Dst,
+                wgpu::BlendFactor::Zero,
+                wgpu::BlendOperation::Add,
+            )),
+            BlendMode::Screen => Some(color(
+                wgpu::BlendFactor::OneMinusDst,
+                wgpu::BlendFactor::One,
+                wgpu::BlendOperation::Add,
+            )),
+            BlendMode::Subtract => Some(color(
+                wgpu::BlendFactor::SrcAlpha,
+                wgpu::BlendFactor::One,
+                wgpu::BlendOperation::ReverseSubtract,
+            )),
+            BlendMode::Opaque => None,
+        }
+    }
+}
+
+impl From<BlendMode> for Option<wgpu::BlendState> {
+    fn from(blend: BlendMode) -> Self {
+        blend.state()
+    }
+}
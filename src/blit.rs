@@ -0,0 +1,263 @@
+use crate::*;
+use std::{collections::HashMap, sync::Arc};
+
+/// How a [Blit] maps the source onto the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlitScaling {
+    /// Stretch the source over the entire target.
+    Stretch,
+    /// Scale by the largest integer factor that fits, centered.
+    ///
+    /// Falls back to [BlitScaling::Letterbox] if the target is smaller than the source.
+    PixelPerfect,
+    /// Scale by the largest factor that fits while preserving aspect ratio, centered.
+    Letterbox,
+}
+
+/// Explicit color space conversion applied by a [Blit].
+///
+/// Sampling from or rendering to an sRGB format already converts automatically,
+/// so this is only needed when the contents of a texture do not match its format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorConversion {
+    None,
+    SrgbToLinear,
+    LinearToSrgb,
+}
+
+impl ColorConversion {
+    fn entry(self) -> &'static str {
+        match self {
+            ColorConversion::None => "fs_main",
+            ColorConversion::SrgbToLinear => "fs_srgb_to_linear",
+            ColorConversion::LinearToSrgb => "fs_linear_to_srgb",
+        }
+    }
+}
+
+/// Describes a copy of a texture into a target view, performed by a [Blitter].
+#[derive(Debug, Clone)]
+pub struct Blit<'a> {
+    pub source: &'a Texture,
+    /// Size of `source` in pixels.
+    pub source_size: (u32, u32),
+    pub target: &'a wgpu::TextureView,
+    pub target_format: wgpu::TextureFormat,
+    /// Size of `target` in pixels.
+    pub target_size: (u32, u32),
+    pub scaling: BlitScaling,
+    pub filter: wgpu::FilterMode,
+    pub conversion: ColorConversion,
+    /// Color of the area of the target not covered by the source.
+    pub border: Color,
+}
+
+/// Copies textures to target views with scaling, filtering and color conversion.
+///
+/// Pipelines are created lazily and cached per target format.
+#[derive(Debug)]
+pub struct Blitter {
+    layout: wgpu::PipelineLayout,
+    groups: Vec<wgpu::BindGroupLayout>,
+    shader: wgpu::ShaderModule,
+    pipelines: HashMap<(wgpu::TextureFormat, ColorConversion), Arc<wgpu::RenderPipeline>>,
+
+    triangle: Mesh,
+    linear: Sampler,
+    nearest: Sampler,
+    texture_binds: BindCache,
+    sampler_binds: BindCache,
+}
+
+impl Blitter {
+    /// Creates a new [Blitter].
+    pub fn new(cx: &Context) -> Self {
+        let (layout, groups) = PipelineLayout(&[
+            BindGroupLayout(&[BUILTIN_TEXTURE_ENTRY]),
+            BindGroupLayout(&[LayoutEntry::Sampler {
+                visible: wgpu::ShaderStages::FRAGMENT,
+                count: None,
+                comparison: false,
+            }]),
+        ])
+        .create(cx);
+
+        let shader = builtin_shader(
+            cx,
+            "e2 blit shader",
+            "e2/blit.wgsl",
+            include_str!("shader/blit.wgsl"),
+        );
+
+        let triangle = Mesh::new(
+            cx,
+            &[
+                Vertex {
+                    pos: [-1., -1.],
+                    uv: [0., 1.],
+                },
+                Vertex {
+                    pos: [3., -1.],
+                    uv: [2., 1.],
+                },
+                Vertex {
+                    pos: [-1., 3.],
+                    uv: [0., -1.],
+                },
+            ],
+            &[0, 1, 2],
+        );
+
+        Blitter {
+            layout,
+            groups,
+            shader,
+            pipelines: HashMap::new(),
+
+            triangle,
+            linear: SimpleSampler::linear_clamp().create(cx),
+            nearest: SimpleSampler::nearest_clamp().create(cx),
+            texture_binds: BindCache::with_label("e2 blit texture bind group"),
+            sampler_binds: BindCache::with_label("e2 blit sampler bind group"),
+        }
+    }
+
+    /// Drops the cached bind groups of the sources blitted so far, which keep their textures alive.
+    ///
+    /// Call this when source textures are replaced, e.g. after resizing them.
+    pub fn clear_sources(&mut self) {
+        self.texture_binds.clear();
+    }
+
+    /// Records `blit` into its own render pass in `frame`.
+    ///
+    /// Nothing is recorded if the target is empty.
+    pub fn blit(&mut self, cx: &Context, frame: &mut Frame, blit: &Blit) {
+        let viewport = blit_viewport(blit.scaling, blit.source_size, blit.target_size);
+        if viewport.size.x <= 0. || viewport.size.y <= 0. {
+            return;
+        }
+
+        let pipeline = self
+            .pipelines
+            .entry((blit.target_format, blit.conversion))
+            .or_insert_with(|| {
+                Arc::new(
+                    SimpleRenderPipeline {
+                        label: Some("e2 blit pipeline"),
+                        layout: Some(&self.layout),
+                        vertex: &self.shader,
+                        fragment: &self.shader,
+                        vertex_entry: "vs_main",
+                        fragment_entry: blit.conversion.entry(),
+                        vertex_layouts: &[Vertex::layout()],
+                        samples: 1,
+                        format: blit.target_format,
+                        blend: None,
+                        extra_targets: &[],
+                        depth_stencil: None,
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        cull_mode: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        alpha_to_coverage: false,
+                    }
+                    .create(cx),
+                )
+            })
+            .clone();
+
+        let texture_group = BindGroup(&[Resource::Texture(blit.source)])
+            .debug_check(&[BUILTIN_TEXTURE_ENTRY])
+            .get(cx, &mut self.texture_binds, &self.groups[0]);
+
+        let sampler = match blit.filter {
+            wgpu::FilterMode::Nearest => &self.nearest,
+            wgpu::FilterMode::Linear => &self.linear,
+        };
+        let sampler_group = BindGroup(&[Resource::Sampler(sampler)]).get(
+            cx,
+            &mut self.sampler_binds,
+            &self.groups[1],
+        );
+
+        let mut pass = SimpleRenderPass {
+            label: Some("e2 blit pass"),
+            target: blit.target,
+            resolve: None,
+            clear: Some(blit.border),
+            extra_targets: &[],
+            depth_stencil: None,
+            format: PassFormat::new(blit.target_format),
+        }
+        .begin(frame);
+
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, texture_group, &[]);
+        pass.set_bind_group(1, sampler_group, &[]);
+        pass.set_viewport(
+            viewport.origin.x,
+            viewport.origin.y,
+            viewport.size.x,
+            viewport.size.y,
+            0.,
+            1.,
+        );
+        pass.set_vertex_buffer(0, self.triangle.vertices.clone(), 0);
+        pass.set_index_buffer(self.triangle.indices.clone(), 0, wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..3, 0, 0..1);
+        cx.stats.draw(1);
+    }
+}
+
+/// Returns the rectangle of the target, in pixels, that the source is drawn into.
+pub fn blit_viewport(scaling: BlitScaling, source: (u32, u32), target: (u32, u32)) -> Rect {
+    let (sw, sh) = (source.0.max(1) as f32, source.1.max(1) as f32);
+    let (tw, th) = (target.0 as f32, target.1 as f32);
+
+    let scale = (tw / sw).min(th / sh);
+    let scale = match scaling {
+        BlitScaling::Stretch => return Rect::new(0., 0., tw, th),
+        BlitScaling::PixelPerfect if scale >= 1. => scale.floor(),
+        BlitScaling::PixelPerfect | BlitScaling::Letterbox => scale,
+    };
+
+    let (w, h) = (sw * scale, sh * scale);
+    Rect::new(((tw - w) / 2.).floor(), ((th - h) / 2.).floor(), w, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_stretch() {
+        let viewport = blit_viewport(BlitScaling::Stretch, (320, 180), (1000, 500));
+        assert_eq!(viewport, Rect::new(0., 0., 1000., 500.));
+    }
+
+    #[test]
+    fn viewport_pixel_perfect() {
+        let viewport = blit_viewport(BlitScaling::PixelPerfect, (320, 180), (1000, 600));
+        assert_eq!(viewport, Rect::new(20., 30., 960., 540.));
+
+        // smaller than the source, so letterboxed instead
+        let viewport = blit_viewport(BlitScaling::PixelPerfect, (320, 180), (160, 160));
+        assert_eq!(viewport, Rect::new(0., 35., 160., 90.));
+    }
+
+    #[test]
+    fn viewport_letterbox() {
+        let viewport = blit_viewport(BlitScaling::Letterbox, (320, 180), (1000, 1000));
+        assert_eq!(viewport.origin, glam::vec2(0., 218.));
+        assert_eq!(viewport.size, glam::vec2(1000., 562.5));
+    }
+
+    #[test]
+    fn viewport_empty() {
+        let viewport = blit_viewport(BlitScaling::Letterbox, (0, 0), (0, 100));
+        assert_eq!(viewport.size.x, 0.);
+        let viewport = blit_viewport(BlitScaling::PixelPerfect, (320, 180), (100, 0));
+        assert_eq!(viewport.size, glam::Vec2::ZERO);
+    }
+}
@@ -2,6 +2,7 @@ use crate::*;
 
 /// Simple sRGB color type with an alpha channel.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "replay", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -21,6 +22,42 @@ impl Color {
     pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Color { r, g, b, a }
     }
+
+    /// Returns the color with its color channels multiplied by alpha.
+    #[inline]
+    pub fn premultiply(self) -> Self {
+        Color::new(self.r * self.a, self.g * self.a, self.b * self.a, self.a)
+    }
+
+    /// Inverse of [Color::premultiply].
+    ///
+    /// Fully transparent colors become transparent black.
+    #[inline]
+    pub fn unpremultiply(self) -> Self {
+        if self.a == 0. {
+            Color::new(0., 0., 0., 0.)
+        } else {
+            Color::new(self.r / self.a, self.g / self.a, self.b / self.a, self.a)
+        }
+    }
+}
+
+/// Converts a single sRGB-encoded channel to linear.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a single linear channel to sRGB encoding.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
 }
 
 impl From<Color> for wgpu::Color {
@@ -1,66 +1,70 @@
 use crate::*;
+use std::path::Path;
 
 /// Stores GPU context handles, most notably the device and queue.
+///
+/// Surfaces are stored separately as [WindowSurface]s, so that a single context can render to many windows.
 #[derive(Debug)]
 pub struct Context {
     pub instance: wgpu::Instance,
-    pub surface: wgpu::Surface,
     pub adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    pub(crate) stats: StatsCounters,
+    /// Built-in pipelines shared by all the renderers of this context.
+    pub pipelines: PipelineCache,
+    optional_features: wgpu::Features,
 }
 
 impl Context {
-    /// Creates a new [Context], rendering to `window`, selecting a backend from `backends`.
+    /// Creates a new [Context] compatible with `window`, selecting a backend from `backends`.
+    ///
+    /// Also returns the [WindowSurface] for `window`.
+    /// Surfaces for other windows can be created with [WindowSurface::new].
+    ///
+    /// Use [ContextBuilder] to configure adapter selection, features and limits.
+    ///
+    /// This blocks on [Context::new_async] and requires the `blocking` feature.
+    #[cfg(feature = "blocking")]
     pub fn new(
         window: &impl raw_window_handle::HasRawWindowHandle,
         backends: wgpu::Backends,
-    ) -> Result<Self> {
-        let instance = wgpu::Instance::new(backends);
-        let surface = unsafe { instance.create_surface(window) };
+    ) -> Result<(Self, WindowSurface)> {
+        ContextBuilder::new(backends).build(window)
+    }
 
-        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        }))
-        .ok_or_else(|| Error::NoSuitableAdapter)?;
-
-        let (device, queue) = pollster::block_on(adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::default(),
-            },
-            None,
-        ))?;
+    /// Asynchronous version of [Context::new], for use within an executor or on the web.
+    pub async fn new_async(
+        window: &impl raw_window_handle::HasRawWindowHandle,
+        backends: wgpu::Backends,
+    ) -> Result<(Self, WindowSurface)> {
+        ContextBuilder::new(backends).build_async(window).await
+    }
 
-        Ok(Context {
-            instance,
-            surface,
-            adapter,
-            device,
-            queue,
-        })
+    /// Returns all the features enabled on the device.
+    #[inline]
+    pub fn features(&self) -> wgpu::Features {
+        self.device.features()
     }
 
-    /// Configures the surface with `width` and `height` in pixels and with `present_mode` presentation.
-    pub fn configure_surface(&self, width: u32, height: u32, present_mode: wgpu::PresentMode) {
-        self.surface.configure(
-            &self.device,
-            &wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format: self.surface.get_supported_formats(&self.adapter)[0],
-                width,
-                height,
-                present_mode,
-            },
-        );
+    /// Returns the optional features (see [ContextBuilder::optional_features]) that were actually granted.
+    #[inline]
+    pub fn optional_features(&self) -> wgpu::Features {
+        self.optional_features
     }
 
-    /// Helper function for [wgpu::Surface::get_current_texture]
-    pub fn next_frame(&self) -> Result<wgpu::SurfaceTexture> {
-        Ok(self.surface.get_current_texture()?)
+    /// Returns the rendering statistics accumulated since the last [Context::take_stats].
+    #[inline]
+    pub fn stats(&self) -> FrameStats {
+        self.stats.snapshot()
+    }
+
+    /// Returns the rendering statistics accumulated since the last call, and resets them.
+    ///
+    /// Call this once per tick, after rendering every window, so that the statistics cover all of their frames.
+    #[inline]
+    pub fn take_stats(&self) -> FrameStats {
+        self.stats.take()
     }
 
     /// Helper function to pad uniform sizes to the next multiple of the minimum uniform buffer alignment.
@@ -73,3 +77,202 @@ impl Context {
         }
     }
 }
+
+type AdapterFilter<'a> = Box<dyn Fn(&wgpu::Adapter) -> bool + 'a>;
+
+/// Builder for [Context], configuring adapter selection and device creation.
+pub struct ContextBuilder<'a> {
+    backends: wgpu::Backends,
+    power_preference: wgpu::PowerPreference,
+    force_fallback_adapter: bool,
+    required_features: wgpu::Features,
+    optional_features: wgpu::Features,
+    limits: wgpu::Limits,
+    adapter_filter: Option<AdapterFilter<'a>>,
+    trace_path: Option<&'a Path>,
+}
+
+impl<'a> ContextBuilder<'a> {
+    /// Creates a new [ContextBuilder] selecting a backend from `backends`.
+    ///
+    /// Defaults to a high performance adapter, no features and default limits.
+    pub fn new(backends: wgpu::Backends) -> Self {
+        ContextBuilder {
+            backends,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default(),
+            adapter_filter: None,
+            trace_path: None,
+        }
+    }
+
+    /// Sets the adapter power preference.
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Forces the use of a fallback (software) adapter.
+    pub fn force_fallback_adapter(mut self, force: bool) -> Self {
+        self.force_fallback_adapter = force;
+        self
+    }
+
+    /// Sets features that the device must support.
+    ///
+    /// Building fails with [Error::UnsupportedFeatures] if the adapter does not support them.
+    pub fn required_features(mut self, features: wgpu::Features) -> Self {
+        self.required_features = features;
+        self
+    }
+
+    /// Sets features that are enabled only if the adapter supports them.
+    ///
+    /// Query which were granted with [Context::optional_features].
+    pub fn optional_features(mut self, features: wgpu::Features) -> Self {
+        self.optional_features = features;
+        self
+    }
+
+    /// Sets the device limits.
+    pub fn limits(mut self, limits: wgpu::Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the device limits to [wgpu::Limits::downlevel_defaults], for weaker adapters.
+    pub fn downlevel_limits(self) -> Self {
+        self.limits(wgpu::Limits::downlevel_defaults())
+    }
+
+    /// Only considers adapters for which `filter` returns `true`.
+    ///
+    /// Among the accepted adapters, the one best matching the power preference is chosen.
+    /// On the web, where adapters cannot be enumerated, the filter only accepts or rejects
+    /// the adapter chosen by the power preference.
+    pub fn adapter_filter(mut self, filter: impl Fn(&wgpu::Adapter) -> bool + 'a) -> Self {
+        self.adapter_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Records an API trace into the directory at `path`.
+    ///
+    /// This has no effect unless the `trace` feature of `wgpu` is enabled.
+    pub fn trace_path(mut self, path: &'a Path) -> Self {
+        self.trace_path = Some(path);
+        self
+    }
+
+    /// Creates a new [Context] compatible with `window`, along with the [WindowSurface] for `window`.
+    ///
+    /// This blocks on [ContextBuilder::build_async] and requires the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    pub fn build(
+        self,
+        window: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<(Context, WindowSurface)> {
+        pollster::block_on(self.build_async(window))
+    }
+
+    /// Asynchronous version of [ContextBuilder::build], for use within an executor or on the web.
+    pub async fn build_async(
+        self,
+        window: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<(Context, WindowSurface)> {
+        let instance = wgpu::Instance::new(self.backends);
+        let surface = unsafe { instance.create_surface(window) };
+        let cx = self.request(instance, Some(&surface)).await?;
+        Ok((cx, WindowSurface::from_surface(surface)))
+    }
+
+    /// Creates a new [Context] without any window, for offscreen rendering into a [RenderTarget].
+    ///
+    /// This blocks on [ContextBuilder::build_headless_async] and requires the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    pub fn build_headless(self) -> Result<Context> {
+        pollster::block_on(self.build_headless_async())
+    }
+
+    /// Asynchronous version of [ContextBuilder::build_headless].
+    pub async fn build_headless_async(self) -> Result<Context> {
+        let instance = wgpu::Instance::new(self.backends);
+        self.request(instance, None).await
+    }
+
+    async fn request(
+        self,
+        instance: wgpu::Instance,
+        surface: Option<&wgpu::Surface>,
+    ) -> Result<Context> {
+        let adapter = match &self.adapter_filter {
+            // adapters cannot be enumerated on the web
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(filter) => instance
+                .enumerate_adapters(self.backends)
+                .filter(|adapter| {
+                    surface.map_or(true, |surface| adapter.is_surface_supported(surface))
+                        && (!self.force_fallback_adapter
+                            || adapter.get_info().device_type == wgpu::DeviceType::Cpu)
+                        && filter(adapter)
+                })
+                .max_by_key(|adapter| {
+                    adapter_rank(adapter.get_info().device_type, self.power_preference)
+                }),
+            _ => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: self.power_preference,
+                    force_fallback_adapter: self.force_fallback_adapter,
+                    compatible_surface: surface,
+                })
+                .await
+                .filter(|adapter| {
+                    self.adapter_filter
+                        .as_ref()
+                        .map_or(true, |filter| filter(adapter))
+                }),
+        }
+        .ok_or(Error::NoSuitableAdapter)?;
+
+        let missing = self.required_features - adapter.features();
+        if !missing.is_empty() {
+            return Err(Error::UnsupportedFeatures(missing));
+        }
+        let optional_features = self.optional_features & adapter.features();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("e2 device"),
+                    features: self.required_features | optional_features,
+                    limits: self.limits,
+                },
+                self.trace_path,
+            )
+            .await?;
+
+        Ok(Context {
+            instance,
+            adapter,
+            device,
+            queue,
+            stats: StatsCounters::default(),
+            pipelines: PipelineCache::new(),
+            optional_features,
+        })
+    }
+}
+
+/// Ranks adapter device types by how well they match `power_preference`; higher is better.
+fn adapter_rank(device_type: wgpu::DeviceType, power_preference: wgpu::PowerPreference) -> u32 {
+    match (device_type, power_preference) {
+        (wgpu::DeviceType::DiscreteGpu, wgpu::PowerPreference::HighPerformance)
+        | (wgpu::DeviceType::IntegratedGpu, wgpu::PowerPreference::LowPower) => 4,
+        (wgpu::DeviceType::DiscreteGpu, _) | (wgpu::DeviceType::IntegratedGpu, _) => 3,
+        (wgpu::DeviceType::VirtualGpu, _) => 2,
+        (wgpu::DeviceType::Cpu, _) => 1,
+        (wgpu::DeviceType::Other, _) => 0,
+    }
+}
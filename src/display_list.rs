@@ -0,0 +1,409 @@
+use crate::*;
+use std::{borrow::Cow, ops::Range, sync::Arc};
+
+/// A single recorded draw command in a [DisplayList].
+#[derive(Debug, Clone)]
+pub enum DisplayCommand {
+    /// A textured or colored sprite.
+    Sprite {
+        /// Texture to sample, or `None` to fill with `color`.
+        texture: Option<Texture>,
+        /// UV sub-rectangle to use.
+        /// Using [Rect::ONE] will mean that the full UV space is available.
+        src_rect: Rect,
+        /// Color to multiply texture color with.
+        color: Color,
+        /// Where to draw the sprite.
+        rect: Rect,
+        /// Rotation (in radians) of the sprite.
+        rotation: f32,
+    },
+    /// A mesh drawn once for each of `draws`.
+    Mesh {
+        mesh: Arc<Mesh>,
+        texture: Texture,
+        draws: Vec<BatchDraw>,
+    },
+}
+
+#[derive(Debug)]
+struct Segment {
+    mesh: Arc<Mesh>,
+    texture: Texture,
+    array: DrawArray<BatchDraw>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Location {
+    segment: usize,
+    first: usize,
+    count: usize,
+}
+
+/// Retained list of draw commands that are compiled into persistent GPU buffers and replayed across frames.
+///
+/// Consecutive commands sharing a mesh and texture are merged into a single instanced draw,
+/// each backed by a [DrawArray]. Replaying the list is thus as cheap as [BatchRenderer::draw_array].
+///
+/// Replacing a command with [DisplayList::set] only re-uploads that command's instances,
+/// unless it no longer fits in its draw (different mesh, texture or instance count).
+///
+/// Only [BitmapFont] text can be recorded. Text drawn with a [FontBrush] is laid out and cached
+/// by its glyph brush, which owns its own atlas and pipeline, so it must still be drawn every frame
+/// with [TextRenderer::draw] after the list is replayed.
+#[derive(Debug)]
+pub struct DisplayList {
+    commands: Vec<DisplayCommand>,
+    matrix: glam::Mat4,
+    rect: Arc<Mesh>,
+    white: Texture,
+
+    segments: Vec<Segment>,
+    locations: Vec<Location>,
+    dirty: Vec<usize>,
+    rebuild: bool,
+}
+
+impl DisplayList {
+    /// Creates a new, empty [DisplayList].
+    pub fn new(cx: &Context) -> Self {
+        let rect = Mesh::new(
+            cx,
+            &[
+                Vertex {
+                    pos: [0., 0.],
+                    uv: [0., 0.],
+                },
+                Vertex {
+                    pos: [1., 0.],
+                    uv: [1., 0.],
+                },
+                Vertex {
+                    pos: [0., 1.],
+                    uv: [0., 1.],
+                },
+                Vertex {
+                    pos: [1., 1.],
+                    uv: [1., 1.],
+                },
+            ],
+            &[0, 2, 1, 2, 3, 1],
+        );
+
+        let white = ImageTexture {
+            label: Some("e2 white texture"),
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            pixels: Cow::Borrowed(&[255, 255, 255, 255]),
+            width: 1,
+            height: 1,
+        }
+        .create(cx);
+
+        DisplayList {
+            commands: Vec::new(),
+            matrix: glam::Mat4::IDENTITY,
+            rect: Arc::new(rect),
+            white,
+
+            segments: Vec::new(),
+            locations: Vec::new(),
+            dirty: Vec::new(),
+            rebuild: false,
+        }
+    }
+
+    /// Sets the matrix that is premultiplied against all the recorded transformations.
+    pub fn set_matrix(&mut self, matrix: glam::Mat4) {
+        if matrix != self.matrix {
+            self.matrix = matrix;
+            self.rebuild = true;
+        }
+    }
+
+    /// Returns the recorded commands.
+    #[inline]
+    pub fn commands(&self) -> &[DisplayCommand] {
+        &self.commands
+    }
+
+    /// Returns the number of recorded commands.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns `true` if there are no recorded commands.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Removes all the recorded commands.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.rebuild = true;
+    }
+
+    /// Records a command, returning its index.
+    pub fn push(&mut self, command: DisplayCommand) -> usize {
+        self.commands.push(command);
+        self.rebuild = true;
+        self.commands.len() - 1
+    }
+
+    /// Replaces the command at `index`, invalidating only that command.
+    pub fn set(&mut self, index: usize, command: DisplayCommand) {
+        self.commands[index] = command;
+        self.dirty.push(index);
+    }
+
+    /// Records a sprite at `rect` with a given `content` and `rotation`, returning its index.
+    ///
+    /// See [SpriteRenderer::draw].
+    pub fn sprite<'a>(
+        &mut self,
+        content: impl Into<SpriteContent<'a>>,
+        rect: Rect,
+        rotation: f32,
+    ) -> usize {
+        self.push(sprite_command(content.into(), rect, rotation))
+    }
+
+    /// Records a single mesh draw, returning its index.
+    ///
+    /// See [MeshRenderer::draw].
+    pub fn mesh(&mut self, mesh: Arc<Mesh>, texture: &Texture, draw: BatchDraw) -> usize {
+        self.batch(mesh, texture, &[draw])
+    }
+
+    /// Records a mesh drawn once for each of `draws`, returning its index.
+    ///
+    /// See [BatchRenderer::draw].
+    pub fn batch(&mut self, mesh: Arc<Mesh>, texture: &Texture, draws: &[BatchDraw]) -> usize {
+        self.push(DisplayCommand::Mesh {
+            mesh,
+            texture: texture.clone(),
+            draws: draws.to_vec(),
+        })
+    }
+
+    /// Records bitmap text as sprites, returning the range of indices of the recorded commands.
+    pub fn text(&mut self, font: &BitmapFont, draw: &BitmapTextDraw) -> Range<usize> {
+        let start = self.commands.len();
+        for (page, sprite) in font.layout(draw) {
+            self.push(DisplayCommand::Sprite {
+                texture: Some(font.pages[page].clone()),
+                src_rect: sprite.src_rect,
+                color: sprite.color,
+                rect: sprite.rect,
+                rotation: sprite.rotation,
+            });
+        }
+        start..self.commands.len()
+    }
+
+    /// Captures the recorded commands into a new [ReplayPass] clearing to `clear`, for dumping to a [Replay].
+    ///
+    /// `texture` and `mesh` return the indices in the [Replay] of the resources the commands reference,
+    /// e.g. by registering them with [Replay::texture] and [Replay::mesh] the first time they are seen.
+    #[cfg(feature = "replay")]
+    pub fn capture(
+        &self,
+        clear: Option<Color>,
+        mut texture: impl FnMut(&Texture) -> usize,
+        mut mesh: impl FnMut(&Arc<Mesh>) -> usize,
+    ) -> ReplayPass {
+        let commands = self
+            .commands
+            .iter()
+            .map(|command| match command {
+                DisplayCommand::Sprite {
+                    texture: sprite_texture,
+                    src_rect,
+                    color,
+                    rect,
+                    rotation,
+                } => ReplayCommand::Sprite {
+                    texture: sprite_texture.as_ref().map(&mut texture),
+                    src_rect: *src_rect,
+                    color: *color,
+                    rect: *rect,
+                    rotation: *rotation,
+                },
+                DisplayCommand::Mesh {
+                    mesh: batch_mesh,
+                    texture: batch_texture,
+                    draws,
+                } => ReplayCommand::Batch {
+                    mesh: mesh(batch_mesh),
+                    texture: texture(batch_texture),
+                    draws: draws.clone(),
+                },
+            })
+            .collect();
+
+        ReplayPass {
+            clear,
+            matrix: self.matrix,
+            commands,
+        }
+    }
+
+    /// Uploads any invalidated commands to the GPU.
+    ///
+    /// This is called automatically by [DisplayList::draw].
+    pub fn compile(&mut self, cx: &Context) {
+        if !self.rebuild {
+            for index in std::mem::take(&mut self.dirty) {
+                if !self.update(cx, index) {
+                    self.rebuild = true;
+                    break;
+                }
+            }
+        }
+
+        if self.rebuild {
+            self.build(cx);
+            self.dirty.clear();
+            self.rebuild = false;
+        }
+    }
+
+    /// Replays the list into `pass` with `renderer`, compiling it first if needed.
+    ///
+    /// The renderer must already be bound to a [BatchRenderPipeline], along with a sampler.
+    pub fn draw(&mut self, cx: &Context, pass: &mut ArenaRenderPass, renderer: &mut BatchRenderer) {
+        self.compile(cx);
+        for segment in &self.segments {
+            renderer.draw_array(cx, pass, &segment.mesh, &segment.texture, &segment.array);
+        }
+    }
+
+    fn key<'a>(&'a self, command: &'a DisplayCommand) -> (&'a Arc<Mesh>, &'a Texture) {
+        match command {
+            DisplayCommand::Sprite { texture, .. } => {
+                (&self.rect, texture.as_ref().unwrap_or(&self.white))
+            }
+            DisplayCommand::Mesh { mesh, texture, .. } => (mesh, texture),
+        }
+    }
+
+    fn instances(&self, command: &DisplayCommand, out: &mut Vec<BatchDraw>) {
+        match command {
+            DisplayCommand::Sprite {
+                src_rect,
+                color,
+                rect,
+                rotation,
+                ..
+            } => out.push(BatchDraw {
+                color: *color,
+                src_rect: *src_rect,
+                transform: self.matrix * rect_matrix(*rect, *rotation),
+            }),
+            DisplayCommand::Mesh { draws, .. } => out.extend(draws.iter().map(|draw| BatchDraw {
+                transform: self.matrix * draw.transform,
+                ..*draw
+            })),
+        }
+    }
+
+    /// Re-uploads a single command in place, returning `false` if the list must be rebuilt instead.
+    fn update(&self, cx: &Context, index: usize) -> bool {
+        let (location, command) = match (self.locations.get(index), self.commands.get(index)) {
+            (Some(location), Some(command)) => (*location, command),
+            _ => return false,
+        };
+
+        let mut draws = Vec::new();
+        self.instances(command, &mut draws);
+        if draws.len() != location.count {
+            return false;
+        }
+        if draws.is_empty() {
+            return true;
+        }
+
+        let segment = &self.segments[location.segment];
+        let (mesh, texture) = self.key(command);
+        if !Arc::ptr_eq(mesh, &segment.mesh) || texture.id() != segment.texture.id() {
+            return false;
+        }
+
+        for (i, draw) in draws.into_iter().enumerate() {
+            segment.array.set(cx, location.first + i, draw);
+        }
+        true
+    }
+
+    fn build(&mut self, cx: &Context) {
+        let mut groups: Vec<(Arc<Mesh>, Texture, Vec<BatchDraw>)> = Vec::new();
+        let mut locations = Vec::with_capacity(self.commands.len());
+
+        for command in &self.commands {
+            let (mesh, texture) = self.key(command);
+            let same = groups
+                .last()
+                .is_some_and(|(m, t, _)| Arc::ptr_eq(m, mesh) && t.id() == texture.id());
+
+            let mut draws = Vec::new();
+            self.instances(command, &mut draws);
+            if draws.is_empty() {
+                locations.push(Location {
+                    segment: 0,
+                    first: 0,
+                    count: 0,
+                });
+                continue;
+            }
+
+            if !same {
+                groups.push((mesh.clone(), texture.clone(), Vec::new()));
+            }
+            let segment = groups.len() - 1;
+            let group = &mut groups[segment].2;
+            locations.push(Location {
+                segment,
+                first: group.len(),
+                count: draws.len(),
+            });
+            group.extend(draws);
+        }
+
+        let mut old = std::mem::take(&mut self.segments).into_iter();
+        self.segments = groups
+            .into_iter()
+            .map(|(mesh, texture, draws)| {
+                let array = match old.next() {
+                    Some(Segment { mut array, .. }) => {
+                        array.update(cx, &draws);
+                        array
+                    }
+                    None => DrawArray::new(cx, &draws),
+                };
+                Segment {
+                    mesh,
+                    texture,
+                    array,
+                }
+            })
+            .collect();
+        self.locations = locations;
+    }
+}
+
+fn sprite_command(content: SpriteContent, rect: Rect, rotation: f32) -> DisplayCommand {
+    let (texture, src_rect, color) = match content {
+        SpriteContent::Textured { texture, src_rect } => {
+            (Some(texture.clone()), src_rect, Color::WHITE)
+        }
+        SpriteContent::Color(color) => (None, Rect::ONE, color),
+    };
+    DisplayCommand::Sprite {
+        texture,
+        src_rect,
+        color,
+        rect,
+        rotation,
+    }
+}
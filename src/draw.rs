@@ -1,9 +1,6 @@
 use crate::*;
 use crevice::std430::AsStd430;
-use std::{
-    marker::PhantomData,
-    sync::atomic::{AtomicU64, Ordering::SeqCst},
-};
+use std::marker::PhantomData;
 use wgpu::util::DeviceExt;
 
 /// Specifies state for a single mesh draw.
@@ -47,6 +44,7 @@ impl<'a> From<&'a MeshDraw<'a>> for GpuDraw {
 
 /// Specifies state for a batched mesh draw.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "replay", derive(serde::Serialize, serde::Deserialize))]
 pub struct BatchDraw {
     /// Color to multiply texture color with.
     /// Using [Color::WHITE] will mean the texture will render as-is.
@@ -88,8 +86,6 @@ pub struct GpuDraw {
     pub transform: mint::ColumnMatrix4<f32>,
 }
 
-static NEXT_DRAW_ARRAY_ID: AtomicU64 = AtomicU64::new(0);
-
 /// An efficient draw data buffer for use with batched renderers.
 ///
 /// This allows for instance data to persist across frames, including
@@ -114,17 +110,19 @@ impl<D: Clone + Into<GpuDraw>> DrawArray<D> {
         let buf = cx
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
+                label: Some("e2 draw array"),
                 contents: unsafe {
                     std::slice::from_raw_parts(draws.as_ptr() as *const u8, size as _)
                 },
                 usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             });
+        cx.stats.buffer_allocated();
+        cx.stats.uploaded(size);
         DrawArray {
             buf,
             len: draws.len(),
             capacity: size,
-            id: NEXT_DRAW_ARRAY_ID.fetch_add(1, SeqCst),
+            id: next_buffer_id(),
             _marker: PhantomData,
         }
     }
@@ -137,18 +135,20 @@ impl<D: Clone + Into<GpuDraw>> DrawArray<D> {
             .collect::<Vec<_>>();
         let size = GpuDraw::std430_size_static() as u64 * draws.len() as u64;
         self.len = draws.len();
+        cx.stats.uploaded(size);
         if size > self.capacity {
+            cx.stats.buffer_allocated();
             self.buf = cx
                 .device
                 .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: None,
+                    label: Some("e2 draw array"),
                     contents: unsafe {
                         std::slice::from_raw_parts(draws.as_ptr() as *const u8, size as _)
                     },
                     usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
                 });
             self.capacity = size;
-            self.id = NEXT_DRAW_ARRAY_ID.fetch_add(1, SeqCst);
+            self.id = next_buffer_id();
         } else {
             cx.queue.write_buffer(&self.buf, 0, unsafe {
                 std::slice::from_raw_parts(draws.as_ptr() as *const u8, size as _)
@@ -160,6 +160,7 @@ impl<D: Clone + Into<GpuDraw>> DrawArray<D> {
     pub fn set(&self, cx: &Context, at: usize, draw: D) {
         assert!(at < self.len);
         let draw = draw.into().as_std430();
+        cx.stats.uploaded(GpuDraw::std430_size_static() as _);
         cx.queue.write_buffer(
             &self.buf,
             at as u64 * GpuDraw::std430_size_static() as u64,
@@ -179,6 +180,12 @@ impl<D: Clone + Into<GpuDraw>> DrawArray<D> {
         self.len
     }
 
+    /// Returns `true` if there are no instances.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Returns the *capacity* of the draw array.
     ///
     /// This is how many more bytes of draw data it can store before needing buffer recreation.
@@ -187,9 +194,9 @@ impl<D: Clone + Into<GpuDraw>> DrawArray<D> {
         self.capacity
     }
 
-    /// Returns an ID uniquely identifying this [DrawArray].
+    /// Returns an ID uniquely identifying the buffer of this [DrawArray], which changes when the buffer is recreated.
     ///
-    /// Primarily for use with [BindCache].
+    /// Primarily for use with [Resource::Buffer]; see [next_buffer_id].
     #[inline]
     pub fn id(&self) -> u64 {
         self.id
@@ -5,6 +5,8 @@ use thiserror::Error;
 pub enum Error {
     #[error("no suitable GPU adapter")]
     NoSuitableAdapter,
+    #[error("GPU adapter does not support required features: {0:?}")]
+    UnsupportedFeatures(wgpu::Features),
     #[error("cannot create logical GPU device")]
     FailedDeviceCreation(#[from] wgpu::RequestDeviceError),
     #[error("invalid image; unable to open")]
@@ -17,6 +19,23 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("error drawing text: {0}")]
     TextDraw(String),
+    #[error("invalid bitmap font: {0}")]
+    InvalidBitmapFont(String),
+    #[error("invalid render graph: {0}")]
+    InvalidRenderGraph(String),
+    #[error("invalid replay: {0}")]
+    InvalidReplay(String),
+    #[error("unsupported replay version {0}")]
+    UnsupportedReplayVersion(u32),
+    #[error("shader error at {file}:{line}:{column}: {message}")]
+    Shader {
+        file: String,
+        line: u32,
+        column: u32,
+        message: String,
+    },
+    #[error("layout mismatch: {0}")]
+    LayoutMismatch(String),
 }
 
 pub type Result<T> = ::core::result::Result<T, Error>;
@@ -13,7 +13,11 @@ pub struct Frame {
 impl Frame {
     /// Creates a new [Frame].
     pub fn new(cx: &Context) -> Self {
-        let cmd = cx.device.create_command_encoder(&Default::default());
+        let cmd = cx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("e2 frame"),
+            });
 
         Frame {
             cmd,
@@ -32,6 +36,7 @@ pub struct FrameArena {
     pub render_pipelines: TypedArena<Arc<wgpu::RenderPipeline>>,
     pub bind_groups: TypedArena<Arc<wgpu::BindGroup>>,
     pub buffers: TypedArena<Arc<wgpu::Buffer>>,
+    pub query_sets: TypedArena<Arc<wgpu::QuerySet>>,
 }
 
 impl FrameArena {
@@ -41,6 +46,13 @@ impl FrameArena {
             render_pipelines: TypedArena::new(),
             bind_groups: TypedArena::new(),
             buffers: TypedArena::new(),
+            query_sets: TypedArena::new(),
         }
     }
 }
+
+impl Default for FrameArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,9 +2,12 @@ use crate::*;
 use std::sync::Arc;
 
 /// Buffer arena that can grow as needed.
+///
+/// Buffers are labelled as `"{label} #{index}"`, where `label` is the label of the descriptor.
 #[derive(Debug)]
 pub struct GrowingBufferArena {
-    buffers: Vec<(Arc<wgpu::Buffer>, u64)>,
+    /// Buffers with their ID and cursor.
+    buffers: Vec<(Arc<wgpu::Buffer>, u64, u64)>,
     desc: wgpu::BufferDescriptor<'static>,
 }
 
@@ -13,10 +16,12 @@ impl GrowingBufferArena {
     ///
     /// The arena will use `desc` to allocate buffers when it grows.
     pub fn new(cx: &Context, desc: wgpu::BufferDescriptor<'static>) -> Self {
-        GrowingBufferArena {
-            buffers: vec![(Arc::new(cx.device.create_buffer(&desc)), 0)],
+        let mut arena = GrowingBufferArena {
+            buffers: Vec::new(),
             desc,
-        }
+        };
+        arena.grow(cx);
+        arena
     }
 
     /// Allocates a sub-buffer of specified `size`.
@@ -25,7 +30,7 @@ impl GrowingBufferArena {
     pub fn allocate(&mut self, cx: &Context, size: u64) -> ArenaAllocation {
         assert!(size <= self.desc.size);
 
-        for (i, (buffer, cursor)) in self.buffers.iter_mut().enumerate() {
+        for (i, (buffer, id, cursor)) in self.buffers.iter_mut().enumerate() {
             if size <= self.desc.size - *cursor {
                 let offset = *cursor;
                 *cursor += size;
@@ -33,6 +38,7 @@ impl GrowingBufferArena {
                     buffer: buffer.clone(),
                     offset,
                     index: i,
+                    id: *id,
                 };
             }
         }
@@ -45,14 +51,23 @@ impl GrowingBufferArena {
     ///
     /// All `ArenaAllocation`s returned from this arena should now be considered invalid.
     pub fn free(&mut self) {
-        for (_, cursor) in &mut self.buffers {
+        for (_, _, cursor) in &mut self.buffers {
             *cursor = 0;
         }
     }
 
     fn grow(&mut self, cx: &Context) {
-        self.buffers
-            .push((Arc::new(cx.device.create_buffer(&self.desc)), 0));
+        cx.stats.buffer_allocated();
+        let label = format!(
+            "{} #{}",
+            self.desc.label.unwrap_or("e2 buffer arena"),
+            self.buffers.len()
+        );
+        let buffer = cx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&label),
+            ..self.desc
+        });
+        self.buffers.push((Arc::new(buffer), next_buffer_id(), 0));
     }
 }
 
@@ -63,4 +78,6 @@ pub struct ArenaAllocation {
     pub offset: u64,
     /// The internal buffer index in [GrowingBufferArena].
     pub index: usize,
+    /// Unique ID of the buffer, for use with [Resource::Buffer].
+    pub id: u64,
 }
@@ -1,7 +1,27 @@
 use crate::*;
-use std::num::{NonZeroU32, NonZeroU64};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    num::{NonZeroU32, NonZeroU64},
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+static NEXT_BUFFER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Returns a new ID, unique within the process, to identify a buffer in [Resource::Buffer].
+///
+/// [DrawArray::id] and [ArenaAllocation::id] are allocated from the same counter.
+pub fn next_buffer_id() -> u64 {
+    NEXT_BUFFER_ID.fetch_add(1, SeqCst)
+}
 
 /// Simplified bind group layout entry.
+///
+/// `count` makes the entry a binding array of that many resources,
+/// which requires the binding array features of [wgpu::Features] for the resource type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LayoutEntry {
     UniformBuffer {
@@ -24,11 +44,33 @@ pub enum LayoutEntry {
         dimension: wgpu::TextureViewDimension,
         multisampled: bool,
     },
+    /// Texture of a depth format, sampled with a comparison sampler or loaded with `textureLoad`.
+    DepthTexture {
+        visible: wgpu::ShaderStages,
+        count: Option<NonZeroU32>,
+        dimension: wgpu::TextureViewDimension,
+        multisampled: bool,
+    },
+    /// Texture read or written without a sampler, declared as `texture_storage_*` in WGSL.
+    StorageTexture {
+        visible: wgpu::ShaderStages,
+        count: Option<NonZeroU32>,
+        access: wgpu::StorageTextureAccess,
+        format: wgpu::TextureFormat,
+        dimension: wgpu::TextureViewDimension,
+    },
+    /// Filtering sampler if `comparison` is `false`, otherwise a comparison sampler.
     Sampler {
         visible: wgpu::ShaderStages,
         count: Option<NonZeroU32>,
         comparison: bool,
     },
+    /// Sampler with nearest filtering only,
+    /// required to sample non-filterable textures such as `R32Float` or [wgpu::TextureSampleType::Float] `{ filterable: false }`.
+    NonFilteringSampler {
+        visible: wgpu::ShaderStages,
+        count: Option<NonZeroU32>,
+    },
 }
 
 impl LayoutEntry {
@@ -38,7 +80,10 @@ impl LayoutEntry {
             LayoutEntry::UniformBuffer { visible, .. }
             | LayoutEntry::StorageBuffer { visible, .. }
             | LayoutEntry::Texture { visible, .. }
-            | LayoutEntry::Sampler { visible, .. } => *visible,
+            | LayoutEntry::DepthTexture { visible, .. }
+            | LayoutEntry::StorageTexture { visible, .. }
+            | LayoutEntry::Sampler { visible, .. }
+            | LayoutEntry::NonFilteringSampler { visible, .. } => *visible,
         }
     }
 
@@ -48,9 +93,137 @@ impl LayoutEntry {
             LayoutEntry::UniformBuffer { count, .. }
             | LayoutEntry::StorageBuffer { count, .. }
             | LayoutEntry::Texture { count, .. }
-            | LayoutEntry::Sampler { count, .. } => *count,
+            | LayoutEntry::DepthTexture { count, .. }
+            | LayoutEntry::StorageTexture { count, .. }
+            | LayoutEntry::Sampler { count, .. }
+            | LayoutEntry::NonFilteringSampler { count, .. } => *count,
         }
     }
+
+    /// Checks that `texture` can be bound to this entry.
+    ///
+    /// Textures created by [Texture::new] carry no [TextureInfo],
+    /// so only the kind of entry is checked for them.
+    pub fn check_texture(&self, texture: &Texture) -> Result<()> {
+        let info = match (self, texture.info()) {
+            (
+                LayoutEntry::Texture { .. }
+                | LayoutEntry::DepthTexture { .. }
+                | LayoutEntry::StorageTexture { .. },
+                Some(info),
+            ) => info,
+            (
+                LayoutEntry::Texture { .. }
+                | LayoutEntry::DepthTexture { .. }
+                | LayoutEntry::StorageTexture { .. },
+                None,
+            ) => return Ok(()),
+            _ => return Err(self.mismatch("a texture")),
+        };
+
+        let sample_type = info.format.describe().sample_type;
+        let (usage, dimension, multisampled, compatible) = match *self {
+            LayoutEntry::Texture {
+                ty,
+                dimension,
+                multisampled,
+                ..
+            } => (
+                wgpu::TextureUsages::TEXTURE_BINDING,
+                dimension,
+                multisampled,
+                match (ty, sample_type) {
+                    (
+                        wgpu::TextureSampleType::Float { filterable: false },
+                        wgpu::TextureSampleType::Float { .. } | wgpu::TextureSampleType::Depth,
+                    ) => true,
+                    (ty, sample_type) => ty == sample_type,
+                },
+            ),
+            LayoutEntry::DepthTexture {
+                dimension,
+                multisampled,
+                ..
+            } => (
+                wgpu::TextureUsages::TEXTURE_BINDING,
+                dimension,
+                multisampled,
+                sample_type == wgpu::TextureSampleType::Depth,
+            ),
+            LayoutEntry::StorageTexture {
+                format, dimension, ..
+            } => (
+                wgpu::TextureUsages::STORAGE_BINDING,
+                dimension,
+                false,
+                format == info.format,
+            ),
+            _ => unreachable!(),
+        };
+
+        if !compatible {
+            Err(self.mismatch(&format!("a texture of format {:?}", info.format)))
+        } else if !info.usage.contains(usage) {
+            Err(self.mismatch(&format!("a texture with usage {:?}", info.usage)))
+        } else if info.dimension != dimension {
+            Err(self.mismatch(&format!("a {:?} texture", info.dimension)))
+        } else if multisampled != (info.samples > 1) {
+            Err(self.mismatch(&format!("a texture with {} samples", info.samples)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that `sampler` can be bound to this entry.
+    ///
+    /// Non-filtering samplers can also be bound to filtering entries.
+    /// Samplers created by [Sampler::new] carry no binding type,
+    /// so only the kind of entry is checked for them.
+    pub fn check_sampler(&self, sampler: &Sampler) -> Result<()> {
+        let expected = match *self {
+            LayoutEntry::Sampler {
+                comparison: true, ..
+            } => wgpu::SamplerBindingType::Comparison,
+            LayoutEntry::Sampler {
+                comparison: false, ..
+            } => wgpu::SamplerBindingType::Filtering,
+            LayoutEntry::NonFilteringSampler { .. } => wgpu::SamplerBindingType::NonFiltering,
+            _ => return Err(self.mismatch("a sampler")),
+        };
+
+        match (expected, sampler.ty()) {
+            (_, None) => Ok(()),
+            (expected, Some(ty)) if expected == ty => Ok(()),
+            (wgpu::SamplerBindingType::Filtering, Some(wgpu::SamplerBindingType::NonFiltering)) => {
+                Ok(())
+            }
+            (_, Some(ty)) => Err(self.mismatch(&format!("a {:?} sampler", ty))),
+        }
+    }
+
+    /// Checks that a buffer binding of `size` bytes can be bound to this entry.
+    ///
+    /// A `size` of `None` binds the rest of the buffer, which is not checked against the minimum binding size.
+    pub fn check_buffer(&self, size: Option<NonZeroU64>) -> Result<()> {
+        match *self {
+            LayoutEntry::UniformBuffer {
+                min_binding_size, ..
+            }
+            | LayoutEntry::StorageBuffer {
+                min_binding_size, ..
+            } => match (size, min_binding_size) {
+                (Some(size), Some(min)) if size < min => {
+                    Err(self.mismatch(&format!("a buffer binding of {} bytes", size)))
+                }
+                _ => Ok(()),
+            },
+            _ => Err(self.mismatch("a buffer")),
+        }
+    }
+
+    fn mismatch(&self, found: &str) -> Error {
+        Error::LayoutMismatch(format!("cannot bind {} to {:?}", found, self))
+    }
 }
 
 impl From<LayoutEntry> for wgpu::BindGroupLayoutEntry {
@@ -88,6 +261,25 @@ impl From<LayoutEntry> for wgpu::BindGroupLayoutEntry {
                     view_dimension: dimension,
                     multisampled,
                 },
+                LayoutEntry::DepthTexture {
+                    dimension,
+                    multisampled,
+                    ..
+                } => wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: dimension,
+                    multisampled,
+                },
+                LayoutEntry::StorageTexture {
+                    access,
+                    format,
+                    dimension,
+                    ..
+                } => wgpu::BindingType::StorageTexture {
+                    access,
+                    format,
+                    view_dimension: dimension,
+                },
                 LayoutEntry::Sampler { comparison, .. } => {
                     wgpu::BindingType::Sampler(if comparison {
                         wgpu::SamplerBindingType::Comparison
@@ -95,6 +287,9 @@ impl From<LayoutEntry> for wgpu::BindGroupLayoutEntry {
                         wgpu::SamplerBindingType::Filtering
                     })
                 }
+                LayoutEntry::NonFilteringSampler { .. } => {
+                    wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering)
+                }
             },
             count: entry.count(),
         }
@@ -110,7 +305,7 @@ impl<'a> BindGroupLayout<'a> {
     pub fn create(self, cx: &Context) -> wgpu::BindGroupLayout {
         cx.device
             .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: None,
+                label: Some("e2 bind group layout"),
                 entries: &self
                     .0
                     .iter()
@@ -124,6 +319,248 @@ impl<'a> BindGroupLayout<'a> {
     }
 }
 
+/// Resource of a [BindGroup].
+#[derive(Debug, Clone)]
+pub enum Resource<'a> {
+    /// Buffer binding, along with an ID identifying the buffer in [BindGroup::key],
+    /// obtained from [next_buffer_id], [ArenaAllocation::id] or [DrawArray::id].
+    Buffer(u64, wgpu::BufferBinding<'a>),
+    /// Buffer bindings bound to an entry with a `count`, each along with its buffer ID.
+    BufferArray(&'a [(u64, wgpu::BufferBinding<'a>)]),
+    Texture(&'a Texture),
+    /// Textures bound to an entry with a `count`.
+    TextureArray(&'a [&'a Texture]),
+    Sampler(&'a Sampler),
+    /// Samplers bound to an entry with a `count`.
+    SamplerArray(&'a [&'a Sampler]),
+}
+
+impl<'a> Resource<'a> {
+    /// Returns the identity of the resource in [BindGroup::key].
+    fn key(&self) -> ResourceKey {
+        let buffer = |id: u64, binding: &wgpu::BufferBinding| (id, binding.offset, binding.size);
+        match self {
+            Resource::Buffer(id, binding) => ResourceKey::Buffer(buffer(*id, binding)),
+            Resource::BufferArray(buffers) => ResourceKey::BufferArray(
+                buffers
+                    .iter()
+                    .map(|(id, binding)| buffer(*id, binding))
+                    .collect(),
+            ),
+            Resource::Texture(texture) => ResourceKey::Texture(texture.id()),
+            Resource::TextureArray(textures) => {
+                ResourceKey::TextureArray(textures.iter().map(|texture| texture.id()).collect())
+            }
+            Resource::Sampler(sampler) => ResourceKey::Sampler(sampler.id()),
+            Resource::SamplerArray(samplers) => {
+                ResourceKey::SamplerArray(samplers.iter().map(|sampler| sampler.id()).collect())
+            }
+        }
+    }
+
+    /// Checks that the resource can be bound to `entry`.
+    pub fn check(&self, entry: &LayoutEntry) -> Result<()> {
+        let len = match self {
+            Resource::BufferArray(buffers) => Some(buffers.len()),
+            Resource::TextureArray(textures) => Some(textures.len()),
+            Resource::SamplerArray(samplers) => Some(samplers.len()),
+            _ => None,
+        };
+        if len != entry.count().map(|count| count.get() as usize) {
+            return Err(Error::LayoutMismatch(format!(
+                "cannot bind {} resources to {:?}",
+                len.unwrap_or(1),
+                entry
+            )));
+        }
+
+        match self {
+            Resource::Buffer(_, binding) => entry.check_buffer(binding.size),
+            Resource::BufferArray(buffers) => buffers
+                .iter()
+                .try_for_each(|(_, binding)| entry.check_buffer(binding.size)),
+            Resource::Texture(texture) => entry.check_texture(texture),
+            Resource::TextureArray(textures) => textures
+                .iter()
+                .try_for_each(|texture| entry.check_texture(texture)),
+            Resource::Sampler(sampler) => entry.check_sampler(sampler),
+            Resource::SamplerArray(samplers) => samplers
+                .iter()
+                .try_for_each(|sampler| entry.check_sampler(sampler)),
+        }
+    }
+}
+
+/// Identity of a [Resource]: buffers by ID and range, textures and samplers by ID.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum ResourceKey {
+    Buffer((u64, u64, Option<NonZeroU64>)),
+    BufferArray(Vec<(u64, u64, Option<NonZeroU64>)>),
+    Texture(u64),
+    TextureArray(Vec<u64>),
+    Sampler(u64),
+    SamplerArray(Vec<u64>),
+}
+
+fn hash_keys(keys: impl Iterator<Item = ResourceKey>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    keys.for_each(|key| key.hash(&mut hasher));
+    hasher.finish()
+}
+
+/// Simplified bind group descriptor, pairing with [BindGroupLayout].
+///
+/// Like [BindGroupLayout], bindings are numbered by their index in the slice.
+#[derive(Debug, Clone, Copy)]
+pub struct BindGroup<'a>(pub &'a [Resource<'a>]);
+
+impl<'a> BindGroup<'a> {
+    /// Returns a key identifying the bound resources, for use with [BindCache].
+    ///
+    /// The key is derived from the resource IDs (and buffer ranges), so it is stable for the same resources.
+    pub fn key(&self) -> u64 {
+        hash_keys(self.0.iter().map(Resource::key))
+    }
+
+    /// Checks that the resources match the `entries` of a [BindGroupLayout].
+    pub fn check(&self, entries: &[LayoutEntry]) -> Result<()> {
+        if self.0.len() != entries.len() {
+            return Err(Error::LayoutMismatch(format!(
+                "bind group has {} resources, but the layout has {} entries",
+                self.0.len(),
+                entries.len()
+            )));
+        }
+        for (i, (resource, entry)) in self.0.iter().zip(entries).enumerate() {
+            resource.check(entry).map_err(|err| match err {
+                Error::LayoutMismatch(message) => {
+                    Error::LayoutMismatch(format!("@binding({}): {}", i, message))
+                }
+                err => err,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Panics in debug builds if the resources do not match `entries`; see [BindGroup::check].
+    #[track_caller]
+    pub(crate) fn debug_check(self, entries: &[LayoutEntry]) -> Self {
+        if cfg!(debug_assertions) {
+            if let Err(err) = self.check(entries) {
+                panic!("{}", err);
+            }
+        }
+        self
+    }
+
+    /// Creates a new [wgpu::BindGroup] from the stored resources.
+    pub fn create(self, cx: &Context, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
+        cx.stats.bind_group_created();
+        self.with_descriptor(Some("e2 bind group"), layout, |desc| {
+            cx.device.create_bind_group(desc)
+        })
+    }
+
+    /// Returns the bind group of these resources from `cache`, creating it if needed.
+    ///
+    /// The bind group is labelled by `cache` (see [BindCache::with_label]).
+    pub fn get(
+        self,
+        cx: &Context,
+        cache: &mut BindCache,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Arc<wgpu::BindGroup> {
+        let key = self.key();
+        self.with_descriptor(None, layout, |desc| cache.get(cx, key, desc))
+    }
+
+    /// Same as [BindGroup::create], but first checks the resources against the `entries` that `layout` was created from.
+    pub fn create_checked(
+        self,
+        cx: &Context,
+        layout: &wgpu::BindGroupLayout,
+        entries: &[LayoutEntry],
+    ) -> Result<wgpu::BindGroup> {
+        self.check(entries)?;
+        Ok(self.create(cx, layout))
+    }
+
+    /// Same as [BindGroup::get], but first checks the resources against the `entries` that `layout` was created from.
+    pub fn get_checked(
+        self,
+        cx: &Context,
+        cache: &mut BindCache,
+        layout: &wgpu::BindGroupLayout,
+        entries: &[LayoutEntry],
+    ) -> Result<Arc<wgpu::BindGroup>> {
+        self.check(entries)?;
+        Ok(self.get(cx, cache, layout))
+    }
+
+    fn with_descriptor<T>(
+        self,
+        label: Option<&str>,
+        layout: &wgpu::BindGroupLayout,
+        f: impl FnOnce(&wgpu::BindGroupDescriptor) -> T,
+    ) -> T {
+        let buffers = self
+            .0
+            .iter()
+            .map(|resource| match resource {
+                Resource::BufferArray(buffers) => {
+                    buffers.iter().map(|(_, binding)| binding.clone()).collect()
+                }
+                _ => vec![],
+            })
+            .collect::<Vec<Vec<_>>>();
+        let views = self
+            .0
+            .iter()
+            .map(|resource| match resource {
+                Resource::TextureArray(textures) => textures
+                    .iter()
+                    .map(|texture| texture.view.as_ref())
+                    .collect(),
+                _ => vec![],
+            })
+            .collect::<Vec<Vec<_>>>();
+        let samplers = self
+            .0
+            .iter()
+            .map(|resource| match resource {
+                Resource::SamplerArray(samplers) => samplers
+                    .iter()
+                    .map(|sampler| sampler.sampler.as_ref())
+                    .collect(),
+                _ => vec![],
+            })
+            .collect::<Vec<Vec<_>>>();
+
+        let entries = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, resource)| wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource: match resource {
+                    Resource::Buffer(_, binding) => wgpu::BindingResource::Buffer(binding.clone()),
+                    Resource::BufferArray(_) => wgpu::BindingResource::BufferArray(&buffers[i]),
+                    Resource::Texture(texture) => wgpu::BindingResource::TextureView(&texture.view),
+                    Resource::TextureArray(_) => wgpu::BindingResource::TextureViewArray(&views[i]),
+                    Resource::Sampler(sampler) => wgpu::BindingResource::Sampler(&sampler.sampler),
+                    Resource::SamplerArray(_) => wgpu::BindingResource::SamplerArray(&samplers[i]),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        f(&wgpu::BindGroupDescriptor {
+            label,
+            layout,
+            entries: &entries,
+        })
+    }
+}
+
 /// Simplified pipeline layout descriptor.
 #[derive(Debug, Clone, Copy)]
 pub struct PipelineLayout<'a>(pub &'a [BindGroupLayout<'a>]);
@@ -133,6 +570,18 @@ impl<'a> PipelineLayout<'a> {
     ///
     /// Also returns, at tuple index 1, the [wgpu::BindGroupLayout]s created in the process.
     pub fn create(self, cx: &Context) -> (wgpu::PipelineLayout, Vec<wgpu::BindGroupLayout>) {
+        self.create_with_push_constants(cx, &[])
+    }
+
+    /// Same as [PipelineLayout::create], but with push constant ranges.
+    ///
+    /// Push constants require [wgpu::Features::PUSH_CONSTANTS],
+    /// and are set with [wgpu::RenderPass::set_push_constants] (also available on [ArenaRenderPass]).
+    pub fn create_with_push_constants(
+        self,
+        cx: &Context,
+        push_constant_ranges: &[wgpu::PushConstantRange],
+    ) -> (wgpu::PipelineLayout, Vec<wgpu::BindGroupLayout>) {
         let groups = self
             .0
             .iter()
@@ -142,9 +591,9 @@ impl<'a> PipelineLayout<'a> {
         let layout = cx
             .device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
+                label: Some("e2 pipeline layout"),
                 bind_group_layouts: &groups.iter().collect::<Vec<_>>(),
-                push_constant_ranges: &[],
+                push_constant_ranges,
             });
 
         (layout, groups)
@@ -203,3 +652,186 @@ pub struct VertexLayout<'a> {
     /// Attributes (position, UV, etc) of the vertex layout.
     pub attributes: &'a [VertexAttribute],
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(keys: Vec<ResourceKey>) -> u64 {
+        hash_keys(keys.into_iter())
+    }
+
+    #[test]
+    fn key_buffers() {
+        let size = NonZeroU64::new(64);
+        let a_key = key(vec![ResourceKey::Buffer((1, 0, size))]);
+        assert_eq!(a_key, key(vec![ResourceKey::Buffer((1, 0, size))]));
+        assert_ne!(a_key, key(vec![ResourceKey::Buffer((2, 0, size))]));
+        assert_ne!(a_key, key(vec![ResourceKey::Buffer((1, 64, size))]));
+        assert_ne!(a_key, key(vec![ResourceKey::Buffer((1, 0, None))]));
+        assert_ne!(
+            a_key,
+            key(vec![ResourceKey::BufferArray(vec![(1, 0, size)])])
+        );
+        assert_ne!(
+            key(vec![ResourceKey::BufferArray(vec![
+                (1, 0, size),
+                (2, 0, size)
+            ])]),
+            key(vec![ResourceKey::BufferArray(vec![
+                (2, 0, size),
+                (1, 0, size)
+            ])])
+        );
+    }
+
+    #[test]
+    fn key_textures_and_samplers() {
+        let texture_key = key(vec![ResourceKey::Texture(1)]);
+        assert_eq!(texture_key, key(vec![ResourceKey::Texture(1)]));
+        assert_ne!(texture_key, key(vec![ResourceKey::Texture(2)]));
+        assert_ne!(texture_key, key(vec![ResourceKey::TextureArray(vec![1])]));
+        // textures and samplers are numbered independently
+        assert_ne!(texture_key, key(vec![ResourceKey::Sampler(1)]));
+        assert_ne!(
+            key(vec![ResourceKey::SamplerArray(vec![1, 2])]),
+            key(vec![
+                ResourceKey::SamplerArray(vec![1]),
+                ResourceKey::Sampler(2)
+            ])
+        );
+        assert_ne!(
+            key(vec![ResourceKey::Texture(1), ResourceKey::Sampler(2)]),
+            key(vec![ResourceKey::Sampler(2), ResourceKey::Texture(1)])
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    mod device {
+        use super::*;
+
+        fn context() -> Context {
+            ContextBuilder::new(wgpu::Backends::all())
+                .build_headless()
+                .expect("no GPU adapter")
+        }
+
+        fn buffer(cx: &Context) -> wgpu::Buffer {
+            cx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 256,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        }
+
+        fn binding(buffer: &wgpu::Buffer, offset: u64) -> wgpu::BufferBinding<'_> {
+            wgpu::BufferBinding {
+                buffer,
+                offset,
+                size: NonZeroU64::new(64),
+            }
+        }
+
+        fn texture(cx: &Context, samples: u32) -> Texture {
+            RenderTexture {
+                label: None,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                samples,
+                width: 4,
+                height: 4,
+                binding: samples == 1,
+            }
+            .create(cx)
+        }
+
+        const STORAGE: LayoutEntry = LayoutEntry::StorageBuffer {
+            visible: wgpu::ShaderStages::VERTEX,
+            count: None,
+            dynamic_offset: false,
+            min_binding_size: NonZeroU64::new(64),
+            read_only: true,
+        };
+
+        #[test]
+        #[ignore = "requires a GPU adapter"]
+        fn resource_key() {
+            let cx = context();
+            let buffer = buffer(&cx);
+            let texture = texture(&cx, 1);
+
+            assert_eq!(
+                Resource::Buffer(3, binding(&buffer, 64)).key(),
+                ResourceKey::Buffer((3, 64, NonZeroU64::new(64)))
+            );
+            assert_eq!(
+                Resource::TextureArray(&[&texture, &texture]).key(),
+                ResourceKey::TextureArray(vec![texture.id(), texture.id()])
+            );
+        }
+
+        #[test]
+        #[ignore = "requires a GPU adapter"]
+        fn check() {
+            let cx = context();
+            let buffer = buffer(&cx);
+            let id = next_buffer_id();
+
+            assert!(BindGroup(&[Resource::Buffer(id, binding(&buffer, 0))])
+                .check(&[STORAGE])
+                .is_ok());
+            let small = wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: NonZeroU64::new(16),
+            };
+            assert!(BindGroup(&[Resource::Buffer(id, small)])
+                .check(&[STORAGE])
+                .is_err());
+            assert!(BindGroup(&[]).check(&[STORAGE]).is_err());
+
+            let array = LayoutEntry::StorageBuffer {
+                visible: wgpu::ShaderStages::VERTEX,
+                count: NonZeroU32::new(2),
+                dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(64),
+                read_only: true,
+            };
+            let buffers = [(id, binding(&buffer, 0)), (id, binding(&buffer, 64))];
+            assert!(BindGroup(&[Resource::BufferArray(&buffers)])
+                .check(&[array])
+                .is_ok());
+            assert!(BindGroup(&[Resource::BufferArray(&buffers[..1])])
+                .check(&[array])
+                .is_err());
+            assert!(BindGroup(&[Resource::Buffer(id, binding(&buffer, 0))])
+                .check(&[array])
+                .is_err());
+
+            let single = texture(&cx, 1);
+            let multisampled = texture(&cx, 4);
+            assert!(BindGroup(&[Resource::Texture(&single)])
+                .check(&[BUILTIN_TEXTURE_ENTRY])
+                .is_ok());
+            assert!(BindGroup(&[Resource::Texture(&multisampled)])
+                .check(&[BUILTIN_TEXTURE_ENTRY])
+                .is_err());
+            assert!(BindGroup(&[Resource::Texture(&single)])
+                .check(&[STORAGE])
+                .is_err());
+        }
+
+        #[test]
+        #[ignore = "requires a GPU adapter"]
+        fn create_counts_bind_groups() {
+            let cx = context();
+            let buffer = buffer(&cx);
+            let layout = BindGroupLayout(&[STORAGE]).create(&cx);
+            cx.take_stats();
+
+            BindGroup(&[Resource::Buffer(next_buffer_id(), binding(&buffer, 0))])
+                .create(&cx, &layout);
+            assert_eq!(cx.stats().bind_groups_created, 1);
+        }
+    }
+}
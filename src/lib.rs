@@ -1,30 +1,54 @@
 mod batch_render;
 mod bind_cache;
+mod bitmap_font;
+mod blend;
+mod blit;
 mod color;
 mod context;
+mod display_list;
 mod draw;
 mod error;
 mod frame;
 mod growing;
 mod layout;
+mod material;
 mod math;
 mod mesh;
 mod mesh_render;
+mod params;
 mod pipeline;
+mod pipeline_cache;
+mod post_process;
+mod preprocess;
+mod profiler;
+mod reflect;
+mod render_graph;
 mod render_pass;
+mod render_target;
+#[cfg(feature = "replay")]
+mod replay;
 mod sampler;
+mod shader_watcher;
 mod sprite;
 mod sprite_batch;
+mod stats;
+mod surface;
 mod text;
 mod texture;
 
 pub use crevice;
 pub use glam;
 pub use image;
+pub use naga;
 pub use wgpu;
 pub use wgpu_glyph;
+
+#[cfg(feature = "replay")]
+pub use replay::*;
 pub use {
-    batch_render::*, bind_cache::*, color::*, context::*, draw::*, error::*, frame::*, growing::*,
-    layout::*, math::*, mesh::*, mesh_render::*, pipeline::*, render_pass::*, sampler::*,
-    sprite::*, sprite_batch::*, text::*, texture::*,
+    batch_render::*, bind_cache::*, bitmap_font::*, blend::*, blit::*, color::*, context::*,
+    display_list::*, draw::*, error::*, frame::*, growing::*, layout::*, material::*, math::*,
+    mesh::*, mesh_render::*, pipeline::*, pipeline_cache::*, post_process::*, preprocess::*,
+    profiler::*, reflect::*, render_graph::*, render_pass::*, render_target::*, sampler::*,
+    shader_watcher::*, sprite::*, sprite_batch::*, stats::*, surface::*, text::*, texture::*,
 };
@@ -0,0 +1,194 @@
+use crate::{
+    params::{params_bytes, params_entry, UniformParams},
+    *,
+};
+use crevice::std140::AsStd140;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Simplified descriptor of a [Material], a user-written shader used with the built-in renderers.
+///
+/// The sources are spliced between a prelude and the entry points of e2's own shader,
+/// and may use the directives of [ShaderPreprocessor].
+/// The prelude declares:
+/// - `Draw`, the draw configuration of a mesh or instance (`color`, `src_rect` and `transform`).
+/// - `VertexInput`, and `VertexOutput` with the `uv`, the `local_uv` of the mesh (before `src_rect`) and the `color`.
+/// - The helpers `draw_vertex(input, draw)`, the default vertex transformation, and `sample_texture(uv)`.
+///
+/// The entry points keep the draw, texture and sampler bindings of the renderer in groups 0 to 2.
+/// The uniform parameters are bound at `@group(3) @binding(0)`, which the fragment source must declare
+/// along with their type, e.g. `var<uniform> material: Params;`.
+/// They are uploaded with the std140 layout of `var<uniform>`, so derive [AsStd140](crevice::std140::AsStd140) for them.
+#[derive(Debug, Clone, Copy)]
+pub struct SimpleMaterial<'a> {
+    /// Debug label of the shader.
+    pub label: Option<&'a str>,
+    /// Built-in pipeline the material stands in for.
+    /// [BuiltinPipeline::Mesh] materials are used with [MeshRenderer], and [BuiltinPipeline::Batch] with [BatchRenderer].
+    pub builtin: BuiltinPipeline,
+    /// Source defining `fn material_fragment(in: VertexOutput) -> vec4<f32>`.
+    pub fragment: &'a str,
+    /// Source defining `fn material_vertex(input: VertexInput, draw: Draw) -> VertexOutput`.
+    /// Spliced after `fragment`. If `None` then `draw_vertex` is used.
+    pub vertex: Option<&'a str>,
+}
+
+impl<'a> SimpleMaterial<'a> {
+    /// Compiles a new [Material] from the stored configuration, with initial `params`.
+    ///
+    /// The sources are processed by a [ShaderPreprocessor] and validated;
+    /// errors are located in the files `"fragment"` or `"vertex"`.
+    pub fn create(self, cx: &Context, params: &impl AsStd140) -> Result<Material> {
+        let shader = ShaderPreprocessor::new()
+            .snippet("e2/material", include_str!("shader/material.wgsl"))
+            .snippet(
+                "e2/material_entry",
+                match self.builtin {
+                    BuiltinPipeline::Mesh => include_str!("shader/material_mesh.wgsl"),
+                    BuiltinPipeline::Batch => include_str!("shader/material_batch.wgsl"),
+                },
+            )
+            .snippet("fragment", self.fragment.to_owned())
+            .snippet("vertex", self.vertex.unwrap_or(DEFAULT_VERTEX).to_owned())
+            .process(
+                "material",
+                "#include \"e2/material\"\n#include \"fragment\"\n#include \"vertex\"\n#include \"e2/material_entry\"",
+            )?;
+        shader.validate()?;
+        let shader = shader.create(cx, self.label);
+
+        let params_layout =
+            BindGroupLayout(&[params_entry(wgpu::ShaderStages::VERTEX_FRAGMENT)]).create(cx);
+
+        let groups = cx.pipelines.bind_group_layouts(cx, self.builtin);
+        let layout = cx
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("e2 material pipeline layout"),
+                bind_group_layouts: &[&groups[0], &groups[1], &groups[2], &params_layout],
+                push_constant_ranges: &[],
+            });
+
+        let params = UniformParams::new(cx, PARAMS_LABEL, &params_layout, &params_bytes(params));
+
+        Ok(Material {
+            builtin: self.builtin,
+            layout: Arc::new(layout),
+            shader: Arc::new(shader),
+            params_layout: Arc::new(params_layout),
+            params,
+            pipelines: Default::default(),
+        })
+    }
+}
+
+const PARAMS_LABEL: &str = "e2 material params";
+
+const DEFAULT_VERTEX: &str = "
+fn material_vertex(input: VertexInput, draw: Draw) -> VertexOutput {
+    return draw_vertex(input, draw);
+}
+";
+
+/// User-written shader replacing the built-in pipeline of [MeshRenderer] or [BatchRenderer].
+///
+/// Created with [SimpleMaterial]. Pipelines are created lazily per [PipelineKey].
+#[derive(Debug, Clone)]
+pub struct Material {
+    builtin: BuiltinPipeline,
+    layout: Arc<wgpu::PipelineLayout>,
+    shader: Arc<wgpu::ShaderModule>,
+    params_layout: Arc<wgpu::BindGroupLayout>,
+    params: UniformParams,
+    pipelines: Arc<Mutex<HashMap<PipelineKey, Arc<wgpu::RenderPipeline>>>>,
+}
+
+impl Material {
+    /// Returns the built-in pipeline the material stands in for.
+    #[inline]
+    pub fn builtin(&self) -> BuiltinPipeline {
+        self.builtin
+    }
+
+    /// Replaces the uniform parameters.
+    ///
+    /// The parameters are uploaded immediately, so they apply to every draw of the frame
+    /// (including draws recorded before this call).
+    pub fn set_params(&mut self, cx: &Context, params: &impl AsStd140) {
+        self.params
+            .write(cx, PARAMS_LABEL, &self.params_layout, &params_bytes(params));
+    }
+
+    /// Returns the pipeline of this material matching `key`, creating it if needed.
+    pub fn pipeline(&self, cx: &Context, key: &PipelineKey) -> Arc<wgpu::RenderPipeline> {
+        self.pipelines
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| {
+                Arc::new(
+                    SimpleRenderPipeline {
+                        label: Some("e2 material pipeline"),
+                        layout: Some(&self.layout),
+                        vertex: &self.shader,
+                        fragment: &self.shader,
+                        vertex_entry: "vs_main",
+                        fragment_entry: "fs_main",
+                        vertex_layouts: &[Vertex::layout()],
+                        samples: key.samples,
+                        format: key.format,
+                        blend: key.blend.state(),
+                        extra_targets: &[],
+                        depth_stencil: key.depth_stencil.clone(),
+                        topology: key.topology,
+                        strip_index_format: builtin_strip_index_format(key.topology),
+                        cull_mode: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        alpha_to_coverage: false,
+                    }
+                    .create(cx),
+                )
+            })
+            .clone()
+    }
+
+    /// Binds the material and a [BuiltinPipeline::Mesh] renderer to `pass`, for the proceeding draw calls.
+    ///
+    /// # Panics
+    /// Panics if the material is not a [BuiltinPipeline::Mesh] material.
+    pub fn bind_mesh(
+        &self,
+        cx: &Context,
+        pass: &mut ArenaRenderPass,
+        renderer: &mut impl Slot3MeshRenderer,
+        blend: BlendMode,
+    ) {
+        assert_eq!(self.builtin, BuiltinPipeline::Mesh, "not a mesh material");
+        self.bind(cx, pass, blend);
+        renderer.bind(0, 1, 2);
+    }
+
+    /// Binds the material and a [BuiltinPipeline::Batch] renderer to `pass`, for the proceeding draw calls.
+    ///
+    /// # Panics
+    /// Panics if the material is not a [BuiltinPipeline::Batch] material.
+    pub fn bind_batch(
+        &self,
+        cx: &Context,
+        pass: &mut ArenaRenderPass,
+        renderer: &mut impl Slot3BatchRenderer,
+        blend: BlendMode,
+    ) {
+        assert_eq!(self.builtin, BuiltinPipeline::Batch, "not a batch material");
+        self.bind(cx, pass, blend);
+        renderer.bind(0, 1, 2);
+    }
+
+    fn bind(&self, cx: &Context, pass: &mut ArenaRenderPass, blend: BlendMode) {
+        let pipeline = self.pipeline(cx, &PipelineKey::new(&pass.format, blend));
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(3, self.params.group().clone(), &[]);
+    }
+}
@@ -2,6 +2,7 @@ use crate::*;
 
 /// 2D rectangle type.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "replay", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     /// Origin, usually top-left, of the rectangle.
     pub origin: glam::Vec2,
@@ -4,6 +4,7 @@ use std::sync::Arc;
 /// GPU vertex with position and UV.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "replay", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vertex {
     pub pos: [f32; 2],
     pub uv: [f32; 2],
@@ -40,14 +41,16 @@ impl Mesh {
     /// Creates a new [Mesh] initialized with `vertices` and `indices`.
     pub fn new(cx: &Context, vertices: &[Vertex], indices: &[u32]) -> Self {
         let vb = Self::create_vb(cx, vertices.len() as _);
+        cx.stats.uploaded(std::mem::size_of_val(vertices) as _);
         cx.queue.write_buffer(&vb, 0, unsafe {
             std::slice::from_raw_parts(
                 vertices.as_ptr() as *const u8,
-                vertices.len() * std::mem::size_of::<Vertex>(),
+                std::mem::size_of_val(vertices),
             )
         });
 
         let ib = Self::create_ib(cx, indices.len() as _);
+        cx.stats.uploaded(std::mem::size_of_val(indices) as _);
         cx.queue.write_buffer(&ib, 0, unsafe {
             std::slice::from_raw_parts(indices.as_ptr() as *const u8, indices.len() * 4)
         });
@@ -69,10 +72,11 @@ impl Mesh {
             self.vertices = Arc::new(Self::create_vb(cx, self.vertex_count));
             self.vertex_capacity = self.vertex_count;
         }
+        cx.stats.uploaded(std::mem::size_of_val(vertices) as _);
         cx.queue.write_buffer(&self.vertices, 0, unsafe {
             std::slice::from_raw_parts(
                 vertices.as_ptr() as *const u8,
-                vertices.len() * std::mem::size_of::<Vertex>(),
+                std::mem::size_of_val(vertices),
             )
         });
     }
@@ -84,14 +88,16 @@ impl Mesh {
             self.indices = Arc::new(Self::create_ib(cx, self.index_count));
             self.index_capacity = self.index_count;
         }
+        cx.stats.uploaded(std::mem::size_of_val(indices) as _);
         cx.queue.write_buffer(&self.indices, 0, unsafe {
             std::slice::from_raw_parts(indices.as_ptr() as *const u8, indices.len() * 4)
         });
     }
 
     fn create_vb(cx: &Context, count: u64) -> wgpu::Buffer {
+        cx.stats.buffer_allocated();
         cx.device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
+            label: Some("e2 mesh vertices"),
             size: std::mem::size_of::<Vertex>() as u64 * count,
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
@@ -99,8 +105,9 @@ impl Mesh {
     }
 
     fn create_ib(cx: &Context, count: u64) -> wgpu::Buffer {
+        cx.stats.buffer_allocated();
         cx.device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
+            label: Some("e2 mesh indices"),
             size: 4 * count,
             usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
@@ -19,6 +19,9 @@ pub struct MeshRenderer {
     uniform_slot: u32,
     texture_slot: u32,
     sampler_slot: u32,
+
+    /// Blend mode and pipeline last bound by `set_blend`.
+    bound: Option<(BlendMode, Arc<wgpu::RenderPipeline>)>,
 }
 
 impl MeshRenderer {
@@ -30,7 +33,7 @@ impl MeshRenderer {
         let uniforms = GrowingBufferArena::new(
             cx,
             wgpu::BufferDescriptor {
-                label: None,
+                label: Some("e2 mesh uniforms"),
                 size: cx.pad_uniform_size(GpuDraw::std430_size_static() as _) * 1024,
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false,
@@ -48,13 +51,15 @@ impl MeshRenderer {
             texture_layout,
             sampler_layout,
 
-            uniform_binds: BindCache::new(),
-            texture_binds: BindCache::new(),
-            sampler_binds: BindCache::new(),
+            uniform_binds: BindCache::with_label("e2 mesh uniforms bind group"),
+            texture_binds: BindCache::with_label("e2 mesh texture bind group"),
+            sampler_binds: BindCache::with_label("e2 mesh sampler bind group"),
 
             uniform_slot: 0,
             texture_slot: 1,
             sampler_slot: 2,
+
+            bound: None,
         }
     }
 
@@ -66,23 +71,11 @@ impl MeshRenderer {
     }
 
     /// Binds a sampler for use with the proceeding draw calls.
-    pub fn bind_sampler<'a>(
-        &mut self,
-        cx: &Context,
-        pass: &'a mut wgpu::RenderPass,
-        sampler: &Sampler,
-    ) {
-        let group = self.sampler_binds.get(
+    pub fn bind_sampler(&mut self, cx: &Context, pass: &mut wgpu::RenderPass, sampler: &Sampler) {
+        let group = BindGroup(&[Resource::Sampler(sampler)]).get(
             cx,
-            sampler.id(),
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.sampler_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&sampler.sampler),
-                }],
-            },
+            &mut self.sampler_binds,
+            &self.sampler_layout,
         );
 
         pass.set_bind_group(
@@ -92,6 +85,33 @@ impl MeshRenderer {
         );
     }
 
+    /// Switches `pass` to the built-in pipeline using `blend`, for the proceeding draw calls.
+    ///
+    /// The pipeline matches [ArenaRenderPass::format].
+    /// Does nothing if the pipeline using `blend` is still bound to `pass`.
+    pub fn set_blend(&mut self, cx: &Context, pass: &mut ArenaRenderPass, blend: BlendMode) {
+        let bound = self.bound.as_ref().is_some_and(|(current, pipeline)| {
+            *current == blend && pass.is_pipeline_bound(pipeline)
+        });
+        if !bound {
+            let pipeline = cx.pipelines.get(
+                cx,
+                BuiltinPipeline::Mesh,
+                &PipelineKey::new(&pass.format, blend),
+            );
+            pass.set_pipeline(pipeline.clone());
+            self.bound = Some((blend, pipeline));
+        }
+    }
+
+    /// Binds the built-in pipeline matching [ArenaRenderPass::format] to `pass`, using [BlendMode::Alpha].
+    ///
+    /// This replaces [MeshRenderPipeline::bind] when rendering into passes of varying formats.
+    pub fn bind_pass(&mut self, cx: &Context, pass: &mut ArenaRenderPass) {
+        Slot3MeshRenderer::bind(self, 0, 1, 2);
+        self.set_blend(cx, pass, BlendMode::default());
+    }
+
     /// Draws a textured mesh with `draw` parameters.
     pub fn draw(&mut self, cx: &Context, pass: &mut ArenaRenderPass, draw: MeshDraw) {
         let alloc = self
@@ -104,36 +124,21 @@ impl MeshRenderer {
             alloc.offset,
             gpu_draw.as_std430().as_bytes(),
         );
-
-        let uniform_group = self.uniform_binds.get(
-            cx,
-            alloc.index as _,
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.uniform_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: alloc.buffer.as_ref(),
-                        offset: 0,
-                        size: Some(NonZeroU64::new(GpuDraw::std430_size_static() as _).unwrap()),
-                    }),
-                }],
+        cx.stats.uploaded(GpuDraw::std430_size_static() as _);
+
+        let uniform_group = BindGroup(&[Resource::Buffer(
+            alloc.id,
+            wgpu::BufferBinding {
+                buffer: alloc.buffer.as_ref(),
+                offset: 0,
+                size: Some(NonZeroU64::new(GpuDraw::std430_size_static() as _).unwrap()),
             },
-        );
+        )])
+        .get(cx, &mut self.uniform_binds, &self.uniform_layout);
 
-        let texture_group = self.texture_binds.get(
-            cx,
-            draw.texture.id(),
-            &wgpu::BindGroupDescriptor {
-                label: None,
-                layout: &self.texture_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&draw.texture.view),
-                }],
-            },
-        );
+        let texture_group = BindGroup(&[Resource::Texture(draw.texture)])
+            .debug_check(&[BUILTIN_TEXTURE_ENTRY])
+            .get(cx, &mut self.texture_binds, &self.texture_layout);
 
         pass.set_bind_group(self.uniform_slot, uniform_group, &[alloc.offset as u32]);
         pass.set_bind_group(self.texture_slot, texture_group, &[]);
@@ -142,6 +147,7 @@ impl MeshRenderer {
         pass.set_index_buffer(draw.mesh.indices.clone(), 0, wgpu::IndexFormat::Uint32);
 
         pass.draw_indexed(0..draw.mesh.index_count as u32, 0, 0..1);
+        cx.stats.draw(1);
     }
 }
 
@@ -161,6 +167,8 @@ impl Slot3MeshRenderer for MeshRenderer {
         self.uniform_slot = uniform;
         self.texture_slot = texture;
         self.sampler_slot = sampler;
+        // the pass now uses whichever pipeline is being bound
+        self.bound = None;
     }
 }
 
@@ -169,10 +177,13 @@ impl Slot3MeshRenderer for MeshRenderer {
 pub struct MeshRenderPipeline {
     pub layout: Arc<wgpu::PipelineLayout>,
     pub pipeline: Arc<wgpu::RenderPipeline>,
+    format: PassFormat,
 }
 
 impl MeshRenderPipeline {
     /// Creates a new [MeshRenderPipeline] with the given parameters.
+    ///
+    /// The shader and pipeline layout are shared through [Context::pipelines], so this is cheap to call repeatedly.
     pub fn new(
         cx: &Context,
         samples: u32,
@@ -180,57 +191,46 @@ impl MeshRenderPipeline {
         blend: Option<wgpu::BlendState>,
         depth_stencil: Option<wgpu::DepthStencilState>,
     ) -> Self {
-        let (layout, _) = PipelineLayout(&[
-            BindGroupLayout(&[LayoutEntry::UniformBuffer {
-                visible: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                count: None,
-                dynamic_offset: true,
-                min_binding_size: Some(
-                    NonZeroU64::new(GpuDraw::std430_size_static() as _).unwrap(),
-                ),
-            }]),
-            BindGroupLayout(&[LayoutEntry::Texture {
-                visible: wgpu::ShaderStages::FRAGMENT,
-                count: None,
-                ty: wgpu::TextureSampleType::Float { filterable: true },
-                dimension: wgpu::TextureViewDimension::D2,
-                multisampled: false,
-            }]),
-            BindGroupLayout(&[LayoutEntry::Sampler {
-                visible: wgpu::ShaderStages::FRAGMENT,
-                count: None,
-                comparison: false,
-            }]),
-        ])
-        .create(cx);
-
-        let shader = cx
-            .device
-            .create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(include_str!("shader/mesh.wgsl").into()),
-            });
-
-        let pipeline = SimpleRenderPipeline {
-            layout: Some(&layout),
-            vertex: &shader,
-            fragment: &shader,
-            vertex_entry: "vs_main",
-            fragment_entry: "fs_main",
-            vertex_layout: Vertex::layout(),
-            samples,
+        let pipeline = cx.pipelines.create(
+            cx,
+            BuiltinPipeline::Mesh,
             format,
+            samples,
             blend,
-            depth_stencil,
-        }
-        .create(cx);
+            depth_stencil.clone(),
+            wgpu::PrimitiveTopology::TriangleList,
+            "fs_main",
+        );
 
         MeshRenderPipeline {
-            layout: Arc::new(layout),
+            layout: cx.pipelines.layout(cx, BuiltinPipeline::Mesh),
             pipeline: Arc::new(pipeline),
+            format: PassFormat {
+                format,
+                samples,
+                depth_stencil,
+            },
         }
     }
 
+    /// Returns a variant of this pipeline using `blend`, creating it if needed.
+    ///
+    /// Variants share the pipeline layout, so bind groups remain valid when switching between them.
+    /// They are memoized in [Context::pipelines].
+    pub fn variant(&self, cx: &Context, blend: BlendMode) -> Arc<wgpu::RenderPipeline> {
+        cx.pipelines.get(
+            cx,
+            BuiltinPipeline::Mesh,
+            &PipelineKey::new(&self.format, blend),
+        )
+    }
+
+    /// Returns the attachment configuration this pipeline renders into.
+    #[inline]
+    pub fn format(&self) -> &PassFormat {
+        &self.format
+    }
+
     /// Bind the pipeline and renderer to a given render pass.
     pub fn bind(&self, pass: &mut ArenaRenderPass, renderer: &mut impl Slot3MeshRenderer) {
         pass.set_pipeline(self.pipeline.clone());
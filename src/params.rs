@@ -0,0 +1,98 @@
+use crate::*;
+use crevice::std140::{AsStd140, Std140};
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+/// Layout entry of [UniformParams], visible to the stages `visible`.
+pub(crate) const fn params_entry(visible: wgpu::ShaderStages) -> LayoutEntry {
+    LayoutEntry::UniformBuffer {
+        visible,
+        count: None,
+        dynamic_offset: false,
+        min_binding_size: None,
+    }
+}
+
+/// Uniform parameters of a user-written shader, in a buffer bound alone in a bind group.
+///
+/// The parameters are laid out as std140, which matches `var<uniform>` in WGSL.
+#[derive(Debug, Clone)]
+pub(crate) struct UniformParams {
+    buffer: Arc<wgpu::Buffer>,
+    group: Arc<wgpu::BindGroup>,
+    size: u64,
+}
+
+impl UniformParams {
+    /// Uploads `params` to a new buffer and binds it with `layout`, created from [params_entry].
+    pub fn new(cx: &Context, label: &str, layout: &wgpu::BindGroupLayout, params: &[u8]) -> Self {
+        let contents = padded(params);
+        let buffer = cx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: &contents,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        cx.stats.buffer_allocated();
+        cx.stats.uploaded(contents.len() as _);
+
+        let group = BindGroup(&[Resource::Buffer(
+            next_buffer_id(),
+            buffer.as_entire_buffer_binding(),
+        )])
+        .debug_check(&[params_entry(wgpu::ShaderStages::all())])
+        .create(cx, layout);
+
+        UniformParams {
+            buffer: Arc::new(buffer),
+            group: Arc::new(group),
+            size: contents.len() as _,
+        }
+    }
+
+    /// Returns the bind group of the parameters.
+    #[inline]
+    pub fn group(&self) -> &Arc<wgpu::BindGroup> {
+        &self.group
+    }
+
+    /// Returns `true` if `params` can be written in place with [UniformParams::write].
+    #[inline]
+    pub fn fits(&self, params: &[u8]) -> bool {
+        padded_size(params.len()) == self.size
+    }
+
+    /// Replaces the parameters, writing them in place if they [fit](UniformParams::fits)
+    /// and otherwise creating a new buffer and bind group.
+    pub fn write(
+        &mut self,
+        cx: &Context,
+        label: &str,
+        layout: &wgpu::BindGroupLayout,
+        params: &[u8],
+    ) {
+        if self.fits(params) {
+            cx.queue.write_buffer(&self.buffer, 0, &padded(params));
+            cx.stats.uploaded(self.size);
+        } else {
+            *self = UniformParams::new(cx, label, layout, params);
+        }
+    }
+}
+
+/// Returns the std140 bytes of `params`.
+pub(crate) fn params_bytes(params: &impl AsStd140) -> Vec<u8> {
+    params.as_std140().as_bytes().to_vec()
+}
+
+// uniform bindings must be at least 16 bytes and a multiple of 16 bytes
+fn padded_size(len: usize) -> u64 {
+    (len.max(1).div_ceil(16) * 16) as _
+}
+
+fn padded(params: &[u8]) -> Vec<u8> {
+    let mut contents = params.to_vec();
+    contents.resize(padded_size(params.len()) as _, 0);
+    contents
+}
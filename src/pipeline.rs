@@ -5,48 +5,93 @@ pub use wgpu::TextureViewDimension::*;
 /// Simplified render pipeline descriptor.
 #[derive(Debug, Clone)]
 pub struct SimpleRenderPipeline<'a> {
+    /// Debug label of the pipeline.
+    pub label: Option<&'a str>,
     pub layout: Option<&'a wgpu::PipelineLayout>,
     pub vertex: &'a wgpu::ShaderModule,
     pub fragment: &'a wgpu::ShaderModule,
     pub vertex_entry: &'a str,
     pub fragment_entry: &'a str,
-    pub vertex_layout: VertexLayout<'a>,
+    /// Layouts of the vertex buffers, bound to consecutive slots from 0.
+    ///
+    /// Attributes are assigned shader locations in order, continuing across layouts;
+    /// e.g. a per-instance layout following [Vertex::layout] starts at `@location(2)`.
+    pub vertex_layouts: &'a [VertexLayout<'a>],
     pub samples: u32,
+    /// Format of the first color target.
     pub format: wgpu::TextureFormat,
+    /// Blending of the first color target.
     pub blend: Option<wgpu::BlendState>,
+    /// Color targets following the first, for rendering to multiple attachments
+    /// (see [SimpleRenderPass::extra_targets]).
+    pub extra_targets: &'a [wgpu::ColorTargetState],
     pub depth_stencil: Option<wgpu::DepthStencilState>,
+    pub topology: wgpu::PrimitiveTopology,
+    /// Format of the indices of indexed draws with a strip topology, which delimits strips with the maximum index.
+    /// Must be `None` for list topologies.
+    pub strip_index_format: Option<wgpu::IndexFormat>,
+    /// Faces to cull, if any.
+    pub cull_mode: Option<wgpu::Face>,
+    /// Winding order of front faces.
+    pub front_face: wgpu::FrontFace,
+    /// Whether to derive the sample coverage mask from the alpha of the first color target.
+    /// Only has an effect when `samples > 1`.
+    pub alpha_to_coverage: bool,
 }
 
 impl<'a> SimpleRenderPipeline<'a> {
     /// Creates a new [wgpu::RenderPipeline] from the stored pipeline configuration.
     pub fn create(self, cx: &Context) -> wgpu::RenderPipeline {
+        let mut location = 0;
+        let attributes = self
+            .vertex_layouts
+            .iter()
+            .map(|layout| {
+                layout
+                    .attributes
+                    .iter()
+                    .map(|&attr| {
+                        location += 1;
+                        wgpu::VertexAttribute {
+                            shader_location: location - 1,
+                            ..attr.into()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let targets = std::iter::once(Some(wgpu::ColorTargetState {
+            format: self.format,
+            blend: self.blend,
+            write_mask: wgpu::ColorWrites::all(),
+        }))
+        .chain(self.extra_targets.iter().cloned().map(Some))
+        .collect::<Vec<_>>();
+
         cx.device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
+                label: self.label,
                 layout: self.layout,
                 vertex: wgpu::VertexState {
                     module: self.vertex,
                     entry_point: self.vertex_entry,
-                    buffers: &[wgpu::VertexBufferLayout {
-                        array_stride: self.vertex_layout.stride,
-                        step_mode: self.vertex_layout.step_mode,
-                        attributes: &self
-                            .vertex_layout
-                            .attributes
-                            .iter()
-                            .enumerate()
-                            .map(|(i, &attr)| wgpu::VertexAttribute {
-                                shader_location: i as _,
-                                ..attr.into()
-                            })
-                            .collect::<Vec<_>>(),
-                    }],
+                    buffers: &self
+                        .vertex_layouts
+                        .iter()
+                        .zip(&attributes)
+                        .map(|(layout, attributes)| wgpu::VertexBufferLayout {
+                            array_stride: layout.stride,
+                            step_mode: layout.step_mode,
+                            attributes,
+                        })
+                        .collect::<Vec<_>>(),
                 },
                 primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None,
+                    topology: self.topology,
+                    strip_index_format: self.strip_index_format,
+                    front_face: self.front_face,
+                    cull_mode: self.cull_mode,
                     unclipped_depth: false,
                     polygon_mode: wgpu::PolygonMode::Fill,
                     conservative: false,
@@ -55,16 +100,12 @@ impl<'a> SimpleRenderPipeline<'a> {
                 multisample: wgpu::MultisampleState {
                     count: self.samples,
                     mask: !0,
-                    alpha_to_coverage_enabled: false,
+                    alpha_to_coverage_enabled: self.alpha_to_coverage,
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: self.fragment,
                     entry_point: self.fragment_entry,
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: self.format,
-                        blend: self.blend,
-                        write_mask: wgpu::ColorWrites::all(),
-                    })],
+                    targets: &targets,
                 }),
                 multiview: None,
             })
@@ -0,0 +1,347 @@
+use crate::*;
+use crevice::std430::AsStd430;
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    num::NonZeroU64,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// Attachment configuration of a render pass.
+///
+/// Stored in [ArenaRenderPass::format] so that renderers can select matching pipelines from the [PipelineCache].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassFormat {
+    /// Format of the color attachment.
+    pub format: wgpu::TextureFormat,
+    /// Sample count of the color attachment.
+    pub samples: u32,
+    /// Depth-stencil state pipelines must use with the depth attachment, if any.
+    pub depth_stencil: Option<wgpu::DepthStencilState>,
+}
+
+impl PassFormat {
+    /// Creates a new [PassFormat] with a single-sampled color attachment and no depth attachment.
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        PassFormat {
+            format,
+            samples: 1,
+            depth_stencil: None,
+        }
+    }
+
+    /// Adds a depth attachment of `format`, which pipelines test against with `compare`,
+    /// and write to if `write` is `true`.
+    pub fn with_depth(
+        mut self,
+        format: wgpu::TextureFormat,
+        compare: wgpu::CompareFunction,
+        write: bool,
+    ) -> Self {
+        self.depth_stencil = Some(wgpu::DepthStencilState {
+            format,
+            depth_write_enabled: write,
+            depth_compare: compare,
+            stencil: Default::default(),
+            bias: Default::default(),
+        });
+        self
+    }
+}
+
+/// Layout entry of the textures sampled by the built-in pipelines.
+pub(crate) const BUILTIN_TEXTURE_ENTRY: LayoutEntry = LayoutEntry::Texture {
+    visible: wgpu::ShaderStages::FRAGMENT,
+    count: None,
+    ty: wgpu::TextureSampleType::Float { filterable: true },
+    dimension: wgpu::TextureViewDimension::D2,
+    multisampled: false,
+};
+
+/// Built-in pipelines memoized by the [PipelineCache].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinPipeline {
+    /// Pipeline used by [MeshRenderer], see [MeshRenderPipeline].
+    Mesh,
+    /// Pipeline used by [BatchRenderer], see [BatchRenderPipeline].
+    Batch,
+}
+
+/// Strip index format of the built-in pipelines, which draw the 32-bit indices of [Mesh].
+pub(crate) fn builtin_strip_index_format(
+    topology: wgpu::PrimitiveTopology,
+) -> Option<wgpu::IndexFormat> {
+    match topology {
+        wgpu::PrimitiveTopology::LineStrip | wgpu::PrimitiveTopology::TriangleStrip => {
+            Some(wgpu::IndexFormat::Uint32)
+        }
+        _ => None,
+    }
+}
+
+/// Configuration that a built-in pipeline is memoized by.
+#[derive(Debug, Clone)]
+pub struct PipelineKey {
+    pub format: wgpu::TextureFormat,
+    pub samples: u32,
+    pub blend: BlendMode,
+    pub depth_stencil: Option<wgpu::DepthStencilState>,
+    pub topology: wgpu::PrimitiveTopology,
+}
+
+impl PipelineKey {
+    /// Creates a new [PipelineKey] rendering triangle lists into `pass` with `blend`.
+    pub fn new(pass: &PassFormat, blend: BlendMode) -> Self {
+        PipelineKey {
+            format: pass.format,
+            samples: pass.samples,
+            blend,
+            depth_stencil: pass.depth_stencil.clone(),
+            topology: wgpu::PrimitiveTopology::TriangleList,
+        }
+    }
+
+    // depth bias is made of floats, so compare and hash their bits instead
+    #[allow(clippy::type_complexity)]
+    fn depth_stencil_bits(
+        &self,
+    ) -> Option<(
+        wgpu::TextureFormat,
+        bool,
+        wgpu::CompareFunction,
+        &wgpu::StencilState,
+        (i32, u32, u32),
+    )> {
+        self.depth_stencil.as_ref().map(|state| {
+            (
+                state.format,
+                state.depth_write_enabled,
+                state.depth_compare,
+                &state.stencil,
+                (
+                    state.bias.constant,
+                    state.bias.slope_scale.to_bits(),
+                    state.bias.clamp.to_bits(),
+                ),
+            )
+        })
+    }
+}
+
+impl PartialEq for PipelineKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.format == other.format
+            && self.samples == other.samples
+            && self.blend == other.blend
+            && self.topology == other.topology
+            && self.depth_stencil_bits() == other.depth_stencil_bits()
+    }
+}
+
+impl Eq for PipelineKey {}
+
+impl Hash for PipelineKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.format.hash(state);
+        self.samples.hash(state);
+        self.blend.hash(state);
+        self.topology.hash(state);
+        self.depth_stencil_bits().hash(state);
+    }
+}
+
+/// Shader module and pipeline layout shared by every variant of a built-in pipeline.
+#[derive(Debug)]
+struct BuiltinShader {
+    layout: Arc<wgpu::PipelineLayout>,
+    groups: Vec<wgpu::BindGroupLayout>,
+    shader: Arc<wgpu::ShaderModule>,
+}
+
+/// Builds and memoizes the built-in pipelines.
+///
+/// The WGSL of each built-in pipeline is compiled once, and every variant shares a single pipeline layout,
+/// so bind groups remain valid across variants.
+///
+/// Every [Context] owns a cache in [Context::pipelines].
+#[derive(Debug, Default)]
+pub struct PipelineCache {
+    mesh: OnceLock<BuiltinShader>,
+    batch: OnceLock<BuiltinShader>,
+    pipelines: Mutex<HashMap<(BuiltinPipeline, PipelineKey), Arc<wgpu::RenderPipeline>>>,
+}
+
+impl PipelineCache {
+    /// Creates a new empty [PipelineCache].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the `builtin` pipeline matching `key`, creating it if needed.
+    ///
+    /// The [BlendMode::PremultipliedAlpha] variants use the `fs_premultiplied` entry point,
+    /// which expects premultiplied textures (see [ImageTexture::premultiply]).
+    pub fn get(
+        &self,
+        cx: &Context,
+        builtin: BuiltinPipeline,
+        key: &PipelineKey,
+    ) -> Arc<wgpu::RenderPipeline> {
+        if let Some(pipeline) = self.pipelines.lock().unwrap().get(&(builtin, key.clone())) {
+            return pipeline.clone();
+        }
+
+        let pipeline = Arc::new(self.create(
+            cx,
+            builtin,
+            key.format,
+            key.samples,
+            key.blend.state(),
+            key.depth_stencil.clone(),
+            key.topology,
+            match key.blend {
+                BlendMode::PremultipliedAlpha => "fs_premultiplied",
+                _ => "fs_main",
+            },
+        ));
+        self.pipelines
+            .lock()
+            .unwrap()
+            .entry((builtin, key.clone()))
+            .or_insert(pipeline)
+            .clone()
+    }
+
+    /// Returns the pipeline layout shared by every variant of `builtin`.
+    pub fn layout(&self, cx: &Context, builtin: BuiltinPipeline) -> Arc<wgpu::PipelineLayout> {
+        self.shader(cx, builtin).layout.clone()
+    }
+
+    /// Returns the bind group layouts (draws, texture, sampler) shared by every variant of `builtin`.
+    pub(crate) fn bind_group_layouts(
+        &self,
+        cx: &Context,
+        builtin: BuiltinPipeline,
+    ) -> &[wgpu::BindGroupLayout] {
+        &self.shader(cx, builtin).groups
+    }
+
+    /// Returns the number of memoized pipelines.
+    pub fn len(&self) -> usize {
+        self.pipelines.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no pipelines are memoized.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all the memoized pipelines.
+    ///
+    /// Compiled shaders and layouts are kept.
+    pub fn clear(&self) {
+        self.pipelines.lock().unwrap().clear();
+    }
+
+    /// Creates a `builtin` pipeline with an arbitrary blend state, without memoizing it.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create(
+        &self,
+        cx: &Context,
+        builtin: BuiltinPipeline,
+        format: wgpu::TextureFormat,
+        samples: u32,
+        blend: Option<wgpu::BlendState>,
+        depth_stencil: Option<wgpu::DepthStencilState>,
+        topology: wgpu::PrimitiveTopology,
+        fragment_entry: &str,
+    ) -> wgpu::RenderPipeline {
+        let shared = self.shader(cx, builtin);
+        SimpleRenderPipeline {
+            label: Some(match builtin {
+                BuiltinPipeline::Mesh => "e2 mesh pipeline",
+                BuiltinPipeline::Batch => "e2 batch pipeline",
+            }),
+            layout: Some(&shared.layout),
+            vertex: &shared.shader,
+            fragment: &shared.shader,
+            vertex_entry: "vs_main",
+            fragment_entry,
+            vertex_layouts: &[Vertex::layout()],
+            samples,
+            format,
+            blend,
+            extra_targets: &[],
+            depth_stencil,
+            topology,
+            strip_index_format: builtin_strip_index_format(topology),
+            cull_mode: None,
+            front_face: wgpu::FrontFace::Ccw,
+            alpha_to_coverage: false,
+        }
+        .create(cx)
+    }
+
+    fn shader(&self, cx: &Context, builtin: BuiltinPipeline) -> &BuiltinShader {
+        match builtin {
+            BuiltinPipeline::Mesh => self.mesh.get_or_init(|| {
+                Self::compile(
+                    cx,
+                    "e2 mesh shader",
+                    "e2/mesh.wgsl",
+                    include_str!("shader/mesh.wgsl"),
+                    LayoutEntry::UniformBuffer {
+                        visible: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        count: None,
+                        dynamic_offset: true,
+                        min_binding_size: Some(
+                            NonZeroU64::new(GpuDraw::std430_size_static() as _).unwrap(),
+                        ),
+                    },
+                )
+            }),
+            BuiltinPipeline::Batch => self.batch.get_or_init(|| {
+                Self::compile(
+                    cx,
+                    "e2 batch shader",
+                    "e2/batch.wgsl",
+                    include_str!("shader/batch.wgsl"),
+                    LayoutEntry::StorageBuffer {
+                        visible: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        count: None,
+                        dynamic_offset: false,
+                        min_binding_size: None,
+                        read_only: true,
+                    },
+                )
+            }),
+        }
+    }
+
+    fn compile(
+        cx: &Context,
+        label: &str,
+        file: &str,
+        source: &str,
+        draws: LayoutEntry,
+    ) -> BuiltinShader {
+        let (layout, groups) = PipelineLayout(&[
+            BindGroupLayout(&[draws]),
+            BindGroupLayout(&[BUILTIN_TEXTURE_ENTRY]),
+            BindGroupLayout(&[LayoutEntry::Sampler {
+                visible: wgpu::ShaderStages::FRAGMENT,
+                count: None,
+                comparison: false,
+            }]),
+        ])
+        .create(cx);
+
+        let shader = builtin_shader(cx, label, file, source);
+
+        BuiltinShader {
+            layout: Arc::new(layout),
+            groups,
+            shader: Arc::new(shader),
+        }
+    }
+}
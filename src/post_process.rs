@@ -0,0 +1,597 @@
+use crate::{
+    params::{params_bytes, params_entry, UniformParams},
+    *,
+};
+use crevice::std140::AsStd140;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+static NEXT_EFFECT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// ID of the shader module holding the built-in effects.
+const BUILTIN_MODULE: u64 = u64::MAX;
+
+const PARAMS_LABEL: &str = "e2 post process params";
+
+/// Layout entries of the texture being processed, in group 0.
+const SOURCE_ENTRIES: [LayoutEntry; 2] = [
+    BUILTIN_TEXTURE_ENTRY,
+    LayoutEntry::Sampler {
+        visible: wgpu::ShaderStages::FRAGMENT,
+        count: None,
+        comparison: false,
+    },
+];
+
+/// Full-screen effect applied by a [PostProcess] stack.
+#[derive(Debug, Clone)]
+pub enum PostEffect {
+    /// Separable Gaussian blur, with a standard deviation of `sigma` pixels.
+    Blur { sigma: f32 },
+    /// Blurs the parts brighter than `threshold` (in luminance) and adds them back `intensity` times.
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        sigma: f32,
+    },
+    /// Fades to `color` towards the edges, starting at `radius` (0 to 1) from the center.
+    Vignette {
+        intensity: f32,
+        radius: f32,
+        smoothness: f32,
+        color: Color,
+    },
+    /// Color grading through a lookup texture.
+    ///
+    /// The LUT is a horizontal strip of N slices of N by N pixels (e.g. 256x16),
+    /// where red increases along each slice, green downwards and blue across slices.
+    ColorGrade { lut: Texture, intensity: f32 },
+    /// Splits the red and blue channels by up to `offset` pixels at the edges.
+    ChromaticAberration { offset: f32 },
+    /// Reduces the resolution to blocks of `size` pixels.
+    Pixelate { size: f32 },
+    /// Screen curvature and `scanlines` horizontal scanlines darkened by up to `intensity`.
+    Crt {
+        curvature: f32,
+        scanlines: f32,
+        intensity: f32,
+    },
+    /// User-defined effect; see [CustomEffect].
+    Custom(CustomEffect),
+}
+
+/// User-defined post-processing effect written in WGSL.
+///
+/// The source is appended to a prelude which declares:
+/// - `VertexOutput`, with the `uv` of the fragment.
+/// - `source` and `source_sampler`, the texture being processed.
+/// - `extra`, the texture given by [CustomEffect::with_texture] (or a white texel).
+/// - The helpers `sample_source(uv)`, `sample_extra(uv)` and `texel_size()`.
+///
+/// The uniform parameters are bound at `@group(1) @binding(0)`, which the source must declare
+/// along with their type, e.g. `var<uniform> params: Params;`.
+/// They are uploaded with the std140 layout of `var<uniform>`, so derive [AsStd140](crevice::std140::AsStd140) for them.
+/// The fragment entry point must be `fs_main(in: VertexOutput) -> @location(0) vec4<f32>`.
+#[derive(Debug, Clone)]
+pub struct CustomEffect {
+    module: Arc<wgpu::ShaderModule>,
+    params: Vec<u8>,
+    texture: Option<Texture>,
+    id: u64,
+}
+
+impl CustomEffect {
+    /// Compiles a new [CustomEffect] from WGSL `source`, with initial `params`.
+    ///
+    /// The source is processed by a [ShaderPreprocessor] and validated;
+    /// errors are located in `source`, which is named by `label` (or `"effect"`).
+    pub fn new(
+        cx: &Context,
+        label: Option<&str>,
+        source: &str,
+        params: &impl AsStd140,
+    ) -> Result<Self> {
+        let name = label.unwrap_or("effect");
+        let shader = ShaderPreprocessor::new()
+            .snippet("e2/post", include_str!("shader/post.wgsl"))
+            .snippet(name, source.to_owned())
+            .process(
+                name,
+                &format!("#include \"e2/post\"\n#include \"{}\"", name),
+            )?;
+        shader.validate()?;
+
+        Ok(CustomEffect {
+            module: Arc::new(shader.create(cx, label)),
+            params: params_bytes(params),
+            texture: None,
+            id: NEXT_EFFECT_ID.fetch_add(1, SeqCst),
+        })
+    }
+
+    /// Binds `texture` as `extra` in the shader.
+    pub fn with_texture(mut self, texture: &Texture) -> Self {
+        self.texture = Some(texture.clone());
+        self
+    }
+
+    /// Replaces the uniform parameters.
+    ///
+    /// Pass the effect to [PostProcess::set] for the change to take effect.
+    pub fn set_params(&mut self, params: &impl AsStd140) {
+        self.params = params_bytes(params);
+    }
+}
+
+/// Parameters of the built-in effects; see `shader/post_effects.wgsl`.
+#[derive(AsStd140, Debug, Clone, Copy, PartialEq)]
+struct BuiltinParams {
+    a: mint::Vector4<f32>,
+    b: mint::Vector4<f32>,
+}
+
+impl BuiltinParams {
+    fn new(a: [f32; 4], b: [f32; 4]) -> Self {
+        BuiltinParams {
+            a: a.into(),
+            b: b.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Extra {
+    None,
+    /// The texture the owning effect started from.
+    Input,
+    Texture(Texture),
+}
+
+/// A step of an effect, before its parameters are uploaded.
+#[derive(Debug)]
+struct StepDesc {
+    module: Arc<wgpu::ShaderModule>,
+    module_id: u64,
+    entry: &'static str,
+    params: Vec<u8>,
+    extra: Extra,
+}
+
+impl StepDesc {
+    fn create(self, cx: &Context, layout: &wgpu::BindGroupLayout) -> Step {
+        Step {
+            module: self.module,
+            module_id: self.module_id,
+            entry: self.entry,
+            params: UniformParams::new(cx, PARAMS_LABEL, layout, &self.params),
+            extra: self.extra,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Step {
+    module: Arc<wgpu::ShaderModule>,
+    module_id: u64,
+    entry: &'static str,
+    params: UniformParams,
+    extra: Extra,
+}
+
+impl Step {
+    /// Returns `true` if `desc` only differs from this step by its parameters and extra texture.
+    fn matches(&self, desc: &StepDesc) -> bool {
+        self.module_id == desc.module_id
+            && self.entry == desc.entry
+            && self.params.fits(&desc.params)
+    }
+}
+
+/// Stack of full-screen effects applied to a scene texture.
+///
+/// Effects ping-pong between intermediate textures, and the last one renders into the output,
+/// e.g. the surface. With no effects, the input is simply copied to the output.
+#[derive(Debug)]
+pub struct PostProcess {
+    effects: Vec<(PostEffect, Vec<Step>)>,
+
+    layout: wgpu::PipelineLayout,
+    groups: Vec<wgpu::BindGroupLayout>,
+    builtin: Arc<wgpu::ShaderModule>,
+    pipelines: HashMap<(u64, &'static str, wgpu::TextureFormat), Arc<wgpu::RenderPipeline>>,
+    copy: Step,
+
+    triangle: Mesh,
+    white: Texture,
+    sampler: Sampler,
+    source_binds: BindCache,
+    extra_binds: BindCache,
+
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    targets: Vec<Texture>,
+}
+
+impl PostProcess {
+    /// Creates a new, empty [PostProcess] whose intermediate textures have `format` and size in pixels.
+    pub fn new(cx: &Context, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let (layout, groups) = PipelineLayout(&[
+            BindGroupLayout(&SOURCE_ENTRIES),
+            BindGroupLayout(&[params_entry(wgpu::ShaderStages::FRAGMENT)]),
+            BindGroupLayout(&[BUILTIN_TEXTURE_ENTRY]),
+        ])
+        .create(cx);
+
+        let builtin = Arc::new(builtin_shader(
+            cx,
+            "e2 post process shader",
+            "e2/post_effects.wgsl",
+            concat!(
+                include_str!("shader/post.wgsl"),
+                "\n",
+                include_str!("shader/post_effects.wgsl")
+            ),
+        ));
+
+        let copy = builtin_step(
+            &builtin,
+            "fs_copy",
+            BuiltinParams::new([0.; 4], [0.; 4]),
+            Extra::None,
+        )
+        .create(cx, &groups[1]);
+
+        let triangle = Mesh::new(
+            cx,
+            &[
+                Vertex {
+                    pos: [-1., -1.],
+                    uv: [0., 1.],
+                },
+                Vertex {
+                    pos: [3., -1.],
+                    uv: [2., 1.],
+                },
+                Vertex {
+                    pos: [-1., 3.],
+                    uv: [0., -1.],
+                },
+            ],
+            &[0, 1, 2],
+        );
+
+        let white = ImageTexture {
+            label: Some("e2 white texture"),
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            pixels: Cow::Borrowed(&[255, 255, 255, 255]),
+            width: 1,
+            height: 1,
+        }
+        .create(cx);
+
+        PostProcess {
+            effects: Vec::new(),
+
+            layout,
+            groups,
+            builtin,
+            pipelines: HashMap::new(),
+            copy,
+
+            triangle,
+            white,
+            sampler: SimpleSampler::linear_clamp().create(cx),
+            source_binds: BindCache::with_label("e2 post process source bind group"),
+            extra_binds: BindCache::with_label("e2 post process extra bind group"),
+
+            format,
+            width,
+            height,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Resizes the intermediate textures.
+    ///
+    /// This also drops the cached bind groups, which would otherwise keep the old textures alive.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.targets.clear();
+            self.source_binds.clear();
+            self.extra_binds.clear();
+        }
+    }
+
+    /// Returns the effects in the order they are applied.
+    pub fn effects(&self) -> impl Iterator<Item = &PostEffect> {
+        self.effects.iter().map(|(effect, _)| effect)
+    }
+
+    /// Returns the number of effects.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// Returns `true` if there are no effects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Appends an effect to the stack, returning its index.
+    pub fn push(&mut self, cx: &Context, effect: PostEffect) -> usize {
+        let steps = self.create(cx, &effect);
+        self.effects.push((effect, steps));
+        self.effects.len() - 1
+    }
+
+    /// Replaces the effect at `index`, e.g. to change its parameters.
+    ///
+    /// If `effect` is of the same kind as the replaced effect, its parameters are written into the existing buffers.
+    pub fn set(&mut self, cx: &Context, index: usize, effect: PostEffect) {
+        let descs = self.compile(&effect);
+        let (current, steps) = &mut self.effects[index];
+        if steps.len() == descs.len() && steps.iter().zip(&descs).all(|(s, d)| s.matches(d)) {
+            for (step, desc) in steps.iter_mut().zip(descs) {
+                step.params
+                    .write(cx, PARAMS_LABEL, &self.groups[1], &desc.params);
+                step.extra = desc.extra;
+            }
+            *current = effect;
+        } else {
+            let steps = self.create(cx, &effect);
+            self.effects[index] = (effect, steps);
+        }
+    }
+
+    /// Removes and returns the effect at `index`.
+    pub fn remove(&mut self, index: usize) -> PostEffect {
+        self.effects.remove(index).0
+    }
+
+    /// Removes all the effects.
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Applies the effects to `input`, rendering the result into `output`.
+    pub fn run(
+        &mut self,
+        cx: &Context,
+        frame: &mut Frame,
+        input: &Texture,
+        output: &wgpu::TextureView,
+        output_format: wgpu::TextureFormat,
+    ) {
+        if self.targets.is_empty() {
+            self.targets = (0..3)
+                .map(|_| {
+                    RenderTexture {
+                        label: Some("e2 post process target"),
+                        format: self.format,
+                        samples: 1,
+                        width: self.width,
+                        height: self.height,
+                        binding: true,
+                    }
+                    .create(cx)
+                })
+                .collect();
+        }
+
+        let steps = self
+            .effects
+            .iter()
+            .flat_map(|(_, steps)| steps.iter().enumerate())
+            .collect::<Vec<_>>();
+        let steps = if steps.is_empty() {
+            vec![(0, &self.copy)]
+        } else {
+            steps
+        };
+
+        let mut source = input.clone();
+        let mut effect_input = input.clone();
+        for (i, &(position, step)) in steps.iter().enumerate() {
+            if position == 0 {
+                effect_input = source.clone();
+            }
+
+            let last = i == steps.len() - 1;
+            let format = if last { output_format } else { self.format };
+            let pipeline = self
+                .pipelines
+                .entry((step.module_id, step.entry, format))
+                .or_insert_with(|| {
+                    Arc::new(
+                        SimpleRenderPipeline {
+                            label: Some("e2 post process pipeline"),
+                            layout: Some(&self.layout),
+                            vertex: &self.builtin,
+                            fragment: &step.module,
+                            vertex_entry: "vs_main",
+                            fragment_entry: step.entry,
+                            vertex_layouts: &[Vertex::layout()],
+                            samples: 1,
+                            format,
+                            blend: None,
+                            extra_targets: &[],
+                            depth_stencil: None,
+                            topology: wgpu::PrimitiveTopology::TriangleList,
+                            strip_index_format: None,
+                            cull_mode: None,
+                            front_face: wgpu::FrontFace::Ccw,
+                            alpha_to_coverage: false,
+                        }
+                        .create(cx),
+                    )
+                })
+                .clone();
+
+            let source_group =
+                BindGroup(&[Resource::Texture(&source), Resource::Sampler(&self.sampler)])
+                    .debug_check(&SOURCE_ENTRIES)
+                    .get(cx, &mut self.source_binds, &self.groups[0]);
+
+            let extra = match &step.extra {
+                Extra::None => &self.white,
+                Extra::Input => &effect_input,
+                Extra::Texture(texture) => texture,
+            };
+            let extra_group = BindGroup(&[Resource::Texture(extra)])
+                .debug_check(&[BUILTIN_TEXTURE_ENTRY])
+                .get(cx, &mut self.extra_binds, &self.groups[2]);
+
+            let target = (!last).then(|| {
+                self.targets
+                    .iter()
+                    .find(|target| target.id() != source.id() && target.id() != effect_input.id())
+                    .unwrap()
+                    .clone()
+            });
+
+            {
+                let mut pass = SimpleRenderPass {
+                    label: Some("e2 post process pass"),
+                    target: target.as_ref().map_or(output, |target| &target.view),
+                    resolve: None,
+                    clear: None,
+                    extra_targets: &[],
+                    depth_stencil: None,
+                    format: PassFormat::new(format),
+                }
+                .begin(frame);
+
+                pass.set_pipeline(pipeline);
+                pass.set_bind_group(0, source_group, &[]);
+                pass.set_bind_group(1, step.params.group().clone(), &[]);
+                pass.set_bind_group(2, extra_group, &[]);
+                pass.set_vertex_buffer(0, self.triangle.vertices.clone(), 0);
+                pass.set_index_buffer(self.triangle.indices.clone(), 0, wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..3, 0, 0..1);
+                cx.stats.draw(1);
+            }
+
+            if let Some(target) = target {
+                source = target;
+            }
+        }
+    }
+
+    fn create(&self, cx: &Context, effect: &PostEffect) -> Vec<Step> {
+        self.compile(effect)
+            .into_iter()
+            .map(|desc| desc.create(cx, &self.groups[1]))
+            .collect()
+    }
+
+    fn compile(&self, effect: &PostEffect) -> Vec<StepDesc> {
+        let step = |entry, a, b, extra| {
+            builtin_step(&self.builtin, entry, BuiltinParams::new(a, b), extra)
+        };
+        let blur = |sigma| {
+            [
+                step("fs_blur", [1., 0., sigma, 0.], [0.; 4], Extra::None),
+                step("fs_blur", [0., 1., sigma, 0.], [0.; 4], Extra::None),
+            ]
+        };
+
+        match effect {
+            PostEffect::Blur { sigma } => blur(*sigma).into(),
+            PostEffect::Bloom {
+                threshold,
+                intensity,
+                sigma,
+            } => {
+                let mut steps = vec![step(
+                    "fs_threshold",
+                    [*threshold, 0., 0., 0.],
+                    [0.; 4],
+                    Extra::None,
+                )];
+                steps.extend(blur(*sigma));
+                steps.push(step(
+                    "fs_bloom",
+                    [*intensity, 0., 0., 0.],
+                    [0.; 4],
+                    Extra::Input,
+                ));
+                steps
+            }
+            PostEffect::Vignette {
+                intensity,
+                radius,
+                smoothness,
+                color,
+            } => vec![step(
+                "fs_vignette",
+                [*intensity, *radius, *smoothness, 0.],
+                [color.r, color.g, color.b, color.a],
+                Extra::None,
+            )],
+            PostEffect::ColorGrade { lut, intensity } => vec![step(
+                "fs_lut",
+                [*intensity, 0., 0., 0.],
+                [0.; 4],
+                Extra::Texture(lut.clone()),
+            )],
+            PostEffect::ChromaticAberration { offset } => vec![step(
+                "fs_chromatic",
+                [*offset, 0., 0., 0.],
+                [0.; 4],
+                Extra::None,
+            )],
+            PostEffect::Pixelate { size } => vec![step(
+                "fs_pixelate",
+                [*size, 0., 0., 0.],
+                [0.; 4],
+                Extra::None,
+            )],
+            PostEffect::Crt {
+                curvature,
+                scanlines,
+                intensity,
+            } => vec![step(
+                "fs_crt",
+                [*curvature, *intensity, *scanlines, 0.],
+                [0.; 4],
+                Extra::None,
+            )],
+            PostEffect::Custom(custom) => vec![StepDesc {
+                module: custom.module.clone(),
+                module_id: custom.id,
+                entry: "fs_main",
+                params: custom.params.clone(),
+                extra: match &custom.texture {
+                    Some(texture) => Extra::Texture(texture.clone()),
+                    None => Extra::None,
+                },
+            }],
+        }
+    }
+}
+
+fn builtin_step(
+    module: &Arc<wgpu::ShaderModule>,
+    entry: &'static str,
+    params: BuiltinParams,
+    extra: Extra,
+) -> StepDesc {
+    StepDesc {
+        module: module.clone(),
+        module_id: BUILTIN_MODULE,
+        entry,
+        params: params_bytes(&params),
+        extra,
+    }
+}
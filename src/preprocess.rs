@@ -0,0 +1,430 @@
+use crate::*;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+/// Snippets available to `#include` in every [ShaderPreprocessor].
+pub const BUILTIN_SNIPPETS: &[(&str, &str)] = &[
+    ("e2/draw", include_str!("shader/include/draw.wgsl")),
+    ("e2/color", include_str!("shader/include/color.wgsl")),
+    ("e2/sdf", include_str!("shader/include/sdf.wgsl")),
+];
+
+/// Minimal WGSL preprocessor, used by all the built-in shaders.
+///
+/// Supports the following directives, each on its own line:
+/// - `#include "name"`, which inserts a named snippet once; later includes of the same snippet are ignored.
+/// - `#define NAME` and `#define NAME value`; the value replaces later occurrences of the identifier `NAME`.
+/// - `#undef NAME`.
+/// - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif`.
+///
+/// The built-in snippets are:
+/// - `e2/draw`: the `Draw` struct of the built-in renderers, with `draw_position` and `draw_uv`.
+/// - `e2/color`: `srgb_to_linear`, `linear_to_srgb`, `luminance` and `premultiply`.
+/// - `e2/sdf`: `sdf_circle`, `sdf_box`, `sdf_rounded_box`, `sdf_segment` and `sdf_coverage`.
+#[derive(Debug, Clone)]
+pub struct ShaderPreprocessor {
+    snippets: HashMap<String, Cow<'static, str>>,
+    defines: HashMap<String, String>,
+}
+
+impl Default for ShaderPreprocessor {
+    fn default() -> Self {
+        ShaderPreprocessor {
+            snippets: BUILTIN_SNIPPETS
+                .iter()
+                .map(|&(name, source)| (name.to_owned(), source.into()))
+                .collect(),
+            defines: HashMap::new(),
+        }
+    }
+}
+
+impl ShaderPreprocessor {
+    /// Creates a new [ShaderPreprocessor] with the [BUILTIN_SNIPPETS] and no definitions.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a snippet that can be included as `#include "name"`, replacing any snippet of the same name.
+    pub fn snippet(
+        mut self,
+        name: impl Into<String>,
+        source: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.snippets.insert(name.into(), source.into());
+        self
+    }
+
+    /// Defines `name` before processing, as if by `#define name value`.
+    ///
+    /// An empty `value` only defines `name` for `#ifdef`.
+    pub fn define(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.defines.insert(name.into(), value.into());
+        self
+    }
+
+    /// Processes `source`, named `file` in error messages.
+    pub fn process(&self, file: &str, source: &str) -> Result<ProcessedShader> {
+        let mut state = State {
+            shader: ProcessedShader {
+                source: String::new(),
+                files: vec![],
+                lines: vec![],
+            },
+            included: HashSet::new(),
+            defines: self.defines.clone(),
+        };
+        self.process_file(&mut state, file, source)?;
+        Ok(state.shader)
+    }
+
+    fn process_file(&self, state: &mut State, file: &str, source: &str) -> Result<()> {
+        let file_index = state.shader.files.len();
+        state.shader.files.push(file.to_owned());
+
+        let error = |line: usize, message: String| Error::Shader {
+            file: file.to_owned(),
+            line: line as _,
+            column: 1,
+            message,
+        };
+
+        // (active, seen #else, line) of each open conditional
+        let mut conditions: Vec<(bool, bool, usize)> = vec![];
+
+        for (i, text) in source.lines().enumerate() {
+            let line = i + 1;
+            let active = conditions.iter().all(|&(active, _, _)| active);
+
+            let directive = match text.trim_start().strip_prefix('#') {
+                Some(directive) => directive,
+                None => {
+                    if active {
+                        substitute(&mut state.shader.source, text, &state.defines);
+                        state.shader.source.push('\n');
+                        state.shader.lines.push((file_index, line as _));
+                    }
+                    continue;
+                }
+            };
+
+            let (name, argument) = directive
+                .trim()
+                .split_once(char::is_whitespace)
+                .map_or((directive.trim(), ""), |(name, argument)| {
+                    (name, argument.trim())
+                });
+
+            match name {
+                "ifdef" | "ifndef" => {
+                    let defined = state
+                        .defines
+                        .contains_key(identifier(argument, line, &error)?);
+                    conditions.push((defined == (name == "ifdef"), false, line));
+                }
+                "else" => match conditions.last_mut() {
+                    Some((active, seen_else @ false, _)) => {
+                        *active = !*active;
+                        *seen_else = true;
+                    }
+                    Some(_) => return Err(error(line, "duplicate #else".into())),
+                    None => return Err(error(line, "#else without #ifdef".into())),
+                },
+                "endif" => {
+                    if conditions.pop().is_none() {
+                        return Err(error(line, "#endif without #ifdef".into()));
+                    }
+                }
+                _ if !active => {}
+                "define" => {
+                    let (name, value) = argument
+                        .split_once(char::is_whitespace)
+                        .unwrap_or((argument, ""));
+                    let name = identifier(name, line, &error)?;
+                    state
+                        .defines
+                        .insert(name.to_owned(), value.trim().to_owned());
+                }
+                "undef" => {
+                    state.defines.remove(identifier(argument, line, &error)?);
+                }
+                "include" => {
+                    let snippet = argument
+                        .strip_prefix('"')
+                        .and_then(|argument| argument.strip_suffix('"'))
+                        .ok_or_else(|| error(line, "expected #include \"name\"".into()))?;
+                    let source = self
+                        .snippets
+                        .get(snippet)
+                        .ok_or_else(|| error(line, format!("unknown snippet \"{}\"", snippet)))?;
+                    if state.included.insert(snippet.to_owned()) {
+                        self.process_file(state, snippet, source)?;
+                    }
+                }
+                _ => return Err(error(line, format!("unknown directive #{}", name))),
+            }
+        }
+
+        match conditions.last() {
+            Some(&(_, _, line)) => Err(error(line, "unterminated #ifdef".into())),
+            None => Ok(()),
+        }
+    }
+}
+
+struct State {
+    shader: ProcessedShader,
+    included: HashSet<String>,
+    defines: HashMap<String, String>,
+}
+
+fn identifier<'s>(
+    text: &'s str,
+    line: usize,
+    error: &impl Fn(usize, String) -> Error,
+) -> Result<&'s str> {
+    let valid = text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(text)
+    } else {
+        Err(error(line, format!("invalid identifier \"{}\"", text)))
+    }
+}
+
+/// Appends `text` to `out`, replacing identifiers with their defined values.
+fn substitute(out: &mut String, text: &str, defines: &HashMap<String, String>) {
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        match defines.get(&rest[..end]) {
+            Some(value) if !value.is_empty() => out.push_str(value),
+            _ => out.push_str(&rest[..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+}
+
+/// WGSL produced by a [ShaderPreprocessor], which remembers where each line came from.
+#[derive(Debug, Clone)]
+pub struct ProcessedShader {
+    source: String,
+    files: Vec<String>,
+    lines: Vec<(usize, u32)>,
+}
+
+impl ProcessedShader {
+    /// Returns the processed WGSL.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Maps a 1-based line of the processed source to the file and 1-based line it came from.
+    pub fn map_line(&self, line: u32) -> Option<(&str, u32)> {
+        let &(file, line) = self.lines.get((line as usize).checked_sub(1)?)?;
+        Some((&self.files[file], line))
+    }
+
+    /// Parses and validates the processed source with naga.
+    ///
+    /// Errors are reported as [Error::Shader], located in the original files.
+    pub fn validate(&self) -> Result<naga::Module> {
+        self.parse().map(|(module, _)| module)
+    }
+
+    /// Validates the processed source and reflects its bindings and vertex inputs.
+    pub fn reflect(&self) -> Result<ShaderReflection> {
+        let (module, info) = self.parse()?;
+        ShaderReflection::new(&module, &info)
+    }
+
+    fn parse(&self) -> Result<(naga::Module, naga::valid::ModuleInfo)> {
+        let module = naga::front::wgsl::parse_str(&self.source)
+            .map_err(|err| self.error(err.location(&self.source), err.message().to_owned()))?;
+
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|err| self.error(err.location(&self.source), err.as_inner().to_string()))?;
+
+        Ok((module, info))
+    }
+
+    /// Creates a new [wgpu::ShaderModule] from the processed source.
+    pub fn create(&self, cx: &Context, label: Option<&str>) -> wgpu::ShaderModule {
+        cx.device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label,
+                source: wgpu::ShaderSource::Wgsl(self.source.as_str().into()),
+            })
+    }
+
+    fn error(&self, location: Option<naga::SourceLocation>, message: String) -> Error {
+        let (file, line, column) = location
+            .and_then(|location| {
+                let (file, line) = self.map_line(location.line_number)?;
+                Some((file, line, location.line_position))
+            })
+            .unwrap_or((&self.files[0], 0, 0));
+
+        Error::Shader {
+            file: file.to_owned(),
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+/// Processes and compiles a built-in shader.
+pub(crate) fn builtin_shader(
+    cx: &Context,
+    label: &str,
+    file: &str,
+    source: &str,
+) -> wgpu::ShaderModule {
+    ShaderPreprocessor::new()
+        .process(file, source)
+        .unwrap_or_else(|err| panic!("invalid built-in shader: {}", err))
+        .create(cx, Some(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(preprocessor: &ShaderPreprocessor, source: &str) -> String {
+        preprocessor
+            .process("main", source)
+            .unwrap()
+            .source()
+            .to_owned()
+    }
+
+    fn error(preprocessor: &ShaderPreprocessor, source: &str) -> (String, u32, String) {
+        match preprocessor.process("main", source) {
+            Err(Error::Shader {
+                file,
+                line,
+                message,
+                ..
+            }) => (file, line, message),
+            result => panic!("expected a shader error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn ifdef_else() {
+        let source = "#ifdef A\na\n#else\nnot a\n#endif\n#ifndef B\nnot b\n#endif";
+        let preprocessor = ShaderPreprocessor::new();
+        assert_eq!(process(&preprocessor, source), "not a\nnot b\n");
+        let preprocessor = ShaderPreprocessor::new().define("A", "").define("B", "");
+        assert_eq!(process(&preprocessor, source), "a\n");
+
+        // directives other than conditionals are skipped in inactive blocks
+        let source = "#ifdef A\n#define B\n#bogus\n#endif\n#ifdef B\nb\n#endif";
+        assert_eq!(process(&ShaderPreprocessor::new(), source), "");
+    }
+
+    #[test]
+    fn define_substitution() {
+        let preprocessor = ShaderPreprocessor::new().define("SIZE", "4");
+        let source = "#define SCALE 2.0\nlet a = SIZE * SCALE + SIZE_2;\n#undef SCALE\nSCALE";
+        assert_eq!(
+            process(&preprocessor, source),
+            "let a = 4 * 2.0 + SIZE_2;\nSCALE\n"
+        );
+    }
+
+    #[test]
+    fn include_once() {
+        let preprocessor = ShaderPreprocessor::new()
+            .snippet("a", "#include \"b\"\na")
+            .snippet("b", "b");
+        let source = "#include \"a\"\n#include \"b\"\nmain";
+        assert_eq!(process(&preprocessor, source), "b\na\nmain\n");
+    }
+
+    #[test]
+    fn map_line() {
+        let preprocessor = ShaderPreprocessor::new().snippet("a", "#ifdef A\nx\n#endif\na");
+        let shader = preprocessor
+            .process("main", "first\n#include \"a\"\nlast")
+            .unwrap();
+        assert_eq!(shader.source(), "first\na\nlast\n");
+        assert_eq!(shader.map_line(1), Some(("main", 1)));
+        assert_eq!(shader.map_line(2), Some(("a", 4)));
+        assert_eq!(shader.map_line(3), Some(("main", 3)));
+        assert_eq!(shader.map_line(0), None);
+        assert_eq!(shader.map_line(4), None);
+    }
+
+    #[test]
+    fn directive_errors() {
+        let preprocessor = ShaderPreprocessor::new().snippet("bad", "ok\n#bogus");
+        let cases = [
+            ("x\n#bogus", ("main", 2, "unknown directive #bogus")),
+            ("#ifdef A\nx", ("main", 1, "unterminated #ifdef")),
+            ("#endif", ("main", 1, "#endif without #ifdef")),
+            (
+                "#ifdef A\n#else\n#else\n#endif",
+                ("main", 3, "duplicate #else"),
+            ),
+            ("#define 1A", ("main", 1, "invalid identifier \"1A\"")),
+            (
+                "#include missing",
+                ("main", 1, "expected #include \"name\""),
+            ),
+            (
+                "\n#include \"missing\"",
+                ("main", 2, "unknown snippet \"missing\""),
+            ),
+            ("#include \"bad\"", ("bad", 2, "unknown directive #bogus")),
+        ];
+        for (source, (file, line, message)) in cases {
+            assert_eq!(
+                error(&preprocessor, source),
+                (file.to_owned(), line, message.to_owned()),
+                "{:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn validation_error_location() {
+        let preprocessor =
+            ShaderPreprocessor::new().snippet("a", "fn a() {}\nfn b() -> f32 { return x; }");
+        let shader = preprocessor
+            .process("main", "#include \"a\"\nfn main() {}")
+            .unwrap();
+        match shader.validate() {
+            Err(Error::Shader { file, line, .. }) => assert_eq!((file.as_str(), line), ("a", 2)),
+            result => panic!("expected a shader error, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn builtin_shaders() {
+        for (file, source) in [
+            ("e2/mesh.wgsl", include_str!("shader/mesh.wgsl")),
+            ("e2/batch.wgsl", include_str!("shader/batch.wgsl")),
+            ("e2/blit.wgsl", include_str!("shader/blit.wgsl")),
+        ] {
+            let shader = ShaderPreprocessor::new().process(file, source).unwrap();
+            if let Err(err) = shader.validate() {
+                panic!("{}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use crate::*;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU8, Ordering::SeqCst},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+const MAP_PENDING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+const STATISTICS_TYPES: wgpu::PipelineStatisticsTypes =
+    wgpu::PipelineStatisticsTypes::from_bits_truncate(
+        wgpu::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS.bits()
+            | wgpu::PipelineStatisticsTypes::CLIPPER_INVOCATIONS.bits()
+            | wgpu::PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT.bits()
+            | wgpu::PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS.bits(),
+    );
+const STATISTICS_SIZE: u64 = 4 * wgpu::QUERY_SIZE as u64;
+
+/// Timing of a named scope in a profiled frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilerScope {
+    pub label: String,
+    /// Measured on the GPU when timestamp queries are available, otherwise on the CPU.
+    /// Scopes within a render pass additionally require [wgpu::Features::WRITE_TIMESTAMP_INSIDE_PASSES].
+    pub duration: Duration,
+    /// Scopes nested within this scope.
+    pub children: Vec<ProfilerScope>,
+}
+
+/// Pipeline statistics of a render pass, see [GpuProfiler::begin_statistics].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PipelineStatistics {
+    pub vertex_shader_invocations: u64,
+    pub clipper_invocations: u64,
+    pub clipper_primitives_out: u64,
+    pub fragment_shader_invocations: u64,
+}
+
+/// Profiling results of a single frame.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProfilerFrame {
+    /// Top-level scopes, in the order they were begun.
+    pub scopes: Vec<ProfilerScope>,
+    /// Pipeline statistics of each statistics scope, in the order they were begun.
+    pub statistics: Vec<(String, PipelineStatistics)>,
+    /// Whether the scope durations were measured on the GPU.
+    pub gpu_timing: bool,
+}
+
+struct QueryBuffers {
+    timestamps: Option<Arc<wgpu::QuerySet>>,
+    statistics: Option<Arc<wgpu::QuerySet>>,
+    read: wgpu::Buffer,
+}
+
+impl std::fmt::Debug for QueryBuffers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryBuffers")
+            .field("read", &self.read)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct RawScope {
+    label: String,
+    parent: Option<usize>,
+    /// Index of the timestamp query pair, if timed on the GPU.
+    query: Option<u32>,
+    cpu_begin: Instant,
+    cpu_end: Instant,
+}
+
+#[derive(Debug)]
+struct Recording {
+    queries: Option<QueryBuffers>,
+    scopes: Vec<RawScope>,
+    stack: Vec<usize>,
+    queries_used: u32,
+    statistics: Vec<String>,
+    statistics_open: bool,
+    map_state: Option<Arc<AtomicU8>>,
+}
+
+/// Opt-in GPU profiler measuring named scopes of a [Frame].
+///
+/// When the device has [wgpu::Features::TIMESTAMP_QUERY], scopes are timed with timestamp queries
+/// and their results become available a few frames later, once the GPU has finished the work.
+/// Otherwise, scopes are timed on the CPU as they are encoded.
+///
+/// Scopes are begun either on the [Frame] between render passes, or within a [SimpleRenderPass]
+/// with [GpuProfiler::begin_pass_scope] to time individual renderers.
+///
+/// Render passes can additionally collect pipeline statistics when the device has
+/// [wgpu::Features::PIPELINE_STATISTICS_QUERY].
+#[derive(Debug)]
+pub struct GpuProfiler {
+    max_scopes: u32,
+    timestamps: bool,
+    pass_timestamps: bool,
+    statistics: bool,
+    period: f32,
+    pool: Vec<QueryBuffers>,
+    current: Option<Recording>,
+    in_flight: VecDeque<Recording>,
+}
+
+impl GpuProfiler {
+    /// Creates a new [GpuProfiler] supporting up to `max_scopes` scopes
+    /// (and as many statistics scopes) per frame.
+    ///
+    /// Scopes past the limit are timed on the CPU.
+    pub fn new(cx: &Context, max_scopes: u32) -> Self {
+        let features = cx.features();
+        GpuProfiler {
+            max_scopes,
+            timestamps: features.contains(wgpu::Features::TIMESTAMP_QUERY),
+            pass_timestamps: features.contains(wgpu::Features::WRITE_TIMESTAMP_INSIDE_PASSES),
+            statistics: features.contains(wgpu::Features::PIPELINE_STATISTICS_QUERY),
+            period: cx.queue.get_timestamp_period(),
+            pool: Vec::new(),
+            current: None,
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    /// Returns `true` if scopes are timed on the GPU.
+    #[inline]
+    pub fn gpu_timing(&self) -> bool {
+        self.timestamps
+    }
+
+    /// Begins profiling a new frame.
+    ///
+    /// Call this before any scopes are begun, usually right after [Frame::new].
+    pub fn begin_frame(&mut self, cx: &Context) {
+        let queries = (self.timestamps || self.statistics)
+            .then(|| self.pool.pop().unwrap_or_else(|| self.create_queries(cx)));
+
+        self.current = Some(Recording {
+            queries,
+            scopes: Vec::new(),
+            stack: Vec::new(),
+            queries_used: 0,
+            statistics: Vec::new(),
+            statistics_open: false,
+            map_state: None,
+        });
+    }
+
+    /// Begins a named scope, nested within the currently open scope, if any.
+    ///
+    /// Scopes must not be begun while a render pass is active on `frame`;
+    /// use [GpuProfiler::begin_pass_scope] within render passes instead.
+    pub fn begin_scope(&mut self, frame: &mut Frame, label: impl Into<String>) {
+        self.begin(label.into(), true, |timestamps, query| {
+            frame.cmd.write_timestamp(&timestamps, query)
+        });
+    }
+
+    /// Ends the most recently begun scope.
+    pub fn end_scope(&mut self, frame: &mut Frame) {
+        self.end(|timestamps, query| frame.cmd.write_timestamp(&timestamps, query));
+    }
+
+    /// Begins a named scope within `pass`, nested within the currently open scope, if any.
+    ///
+    /// The scope must be ended with [GpuProfiler::end_pass_scope] before `pass` ends.
+    /// It is timed on the CPU unless the device has [wgpu::Features::WRITE_TIMESTAMP_INSIDE_PASSES].
+    pub fn begin_pass_scope(&mut self, pass: &mut ArenaRenderPass, label: impl Into<String>) {
+        let gpu = self.pass_timestamps;
+        self.begin(label.into(), gpu, |timestamps, query| {
+            pass.write_timestamp(timestamps, query)
+        });
+    }
+
+    /// Ends the most recently begun scope, which must have been begun with [GpuProfiler::begin_pass_scope].
+    pub fn end_pass_scope(&mut self, pass: &mut ArenaRenderPass) {
+        self.end(|timestamps, query| pass.write_timestamp(timestamps, query));
+    }
+
+    /// Runs `f` within a named scope of `pass`.
+    pub fn pass_scope<'a, R>(
+        &mut self,
+        pass: &mut ArenaRenderPass<'a>,
+        label: impl Into<String>,
+        f: impl FnOnce(&mut Self, &mut ArenaRenderPass<'a>) -> R,
+    ) -> R {
+        self.begin_pass_scope(pass, label);
+        let result = f(self, pass);
+        self.end_pass_scope(pass);
+        result
+    }
+
+    fn begin(
+        &mut self,
+        label: String,
+        gpu: bool,
+        write_timestamp: impl FnOnce(Arc<wgpu::QuerySet>, u32),
+    ) {
+        let max_scopes = self.max_scopes;
+        let recording = self
+            .current
+            .as_mut()
+            .expect("GpuProfiler::begin_frame not called");
+
+        let timestamps = recording
+            .queries
+            .as_ref()
+            .and_then(|q| q.timestamps.clone())
+            .filter(|_| gpu && recording.queries_used < max_scopes);
+        let query = timestamps.map(|timestamps| {
+            let query = recording.queries_used;
+            recording.queries_used += 1;
+            write_timestamp(timestamps, query * 2);
+            query
+        });
+
+        let index = recording.scopes.len();
+        let now = Instant::now();
+        recording.scopes.push(RawScope {
+            label,
+            parent: recording.stack.last().copied(),
+            query,
+            cpu_begin: now,
+            cpu_end: now,
+        });
+        recording.stack.push(index);
+    }
+
+    fn end(&mut self, write_timestamp: impl FnOnce(Arc<wgpu::QuerySet>, u32)) {
+        let recording = self
+            .current
+            .as_mut()
+            .expect("GpuProfiler::begin_frame not called");
+
+        let index = recording
+            .stack
+            .pop()
+            .expect("GpuProfiler::end_scope called without an open scope");
+        let scope = &mut recording.scopes[index];
+        if let (Some(query), Some(timestamps)) = (
+            scope.query,
+            recording
+                .queries
+                .as_ref()
+                .and_then(|q| q.timestamps.clone()),
+        ) {
+            write_timestamp(timestamps, query * 2 + 1);
+        }
+        scope.cpu_end = Instant::now();
+    }
+
+    /// Runs `f` within a named scope.
+    pub fn scope<R>(
+        &mut self,
+        frame: &mut Frame,
+        label: impl Into<String>,
+        f: impl FnOnce(&mut Self, &mut Frame) -> R,
+    ) -> R {
+        self.begin_scope(frame, label);
+        let result = f(self, frame);
+        self.end_scope(frame);
+        result
+    }
+
+    /// Begins collecting pipeline statistics for the draws in `pass`.
+    ///
+    /// Statistics scopes cannot be nested.
+    /// Does nothing if the device lacks [wgpu::Features::PIPELINE_STATISTICS_QUERY].
+    pub fn begin_statistics(&mut self, pass: &mut ArenaRenderPass, label: impl Into<String>) {
+        let max_scopes = self.max_scopes as usize;
+        let recording = self
+            .current
+            .as_mut()
+            .expect("GpuProfiler::begin_frame not called");
+        assert!(
+            !recording.statistics_open,
+            "statistics scopes cannot be nested"
+        );
+
+        let index = recording.statistics.len();
+        if let Some(statistics) = recording
+            .queries
+            .as_ref()
+            .and_then(|q| q.statistics.clone())
+        {
+            if index < max_scopes {
+                pass.begin_pipeline_statistics_query(statistics, index as _);
+                recording.statistics.push(label.into());
+                recording.statistics_open = true;
+            }
+        }
+    }
+
+    /// Ends the statistics scope begun with [GpuProfiler::begin_statistics].
+    pub fn end_statistics(&mut self, pass: &mut ArenaRenderPass) {
+        let recording = self
+            .current
+            .as_mut()
+            .expect("GpuProfiler::begin_frame not called");
+        if recording.statistics_open {
+            pass.end_pipeline_statistics_query();
+            recording.statistics_open = false;
+        }
+    }
+
+    /// Ends profiling the current frame, closing any open scopes and resolving the queries.
+    ///
+    /// Call this *before* [Frame::submit].
+    pub fn end_frame(&mut self, frame: &mut Frame) {
+        while self
+            .current
+            .as_ref()
+            .is_some_and(|recording| !recording.stack.is_empty())
+        {
+            self.end_scope(frame);
+        }
+
+        let mut recording = match self.current.take() {
+            Some(recording) => recording,
+            None => return,
+        };
+
+        if let Some(queries) = &recording.queries {
+            let used = recording.queries_used;
+            if let Some(timestamps) = &queries.timestamps {
+                if used > 0 {
+                    frame
+                        .cmd
+                        .resolve_query_set(timestamps, 0..used * 2, &queries.read, 0);
+                }
+            }
+            if let Some(statistics) = &queries.statistics {
+                if !recording.statistics.is_empty() {
+                    frame.cmd.resolve_query_set(
+                        statistics,
+                        0..recording.statistics.len() as u32,
+                        &queries.read,
+                        self.statistics_offset(),
+                    );
+                }
+            }
+        }
+
+        recording.statistics_open = false;
+        self.in_flight.push_back(recording);
+    }
+
+    /// Returns the results of the most recent frame that has finished on the GPU since the last call, if any.
+    ///
+    /// Call this *after* [Frame::submit], once per frame.
+    pub fn collect(&mut self, cx: &Context) -> Option<ProfilerFrame> {
+        for recording in &mut self.in_flight {
+            if let (Some(queries), None) = (&recording.queries, &recording.map_state) {
+                let state = Arc::new(AtomicU8::new(MAP_PENDING));
+                let callback_state = state.clone();
+                queries
+                    .read
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        callback_state
+                            .store(if result.is_ok() { MAP_DONE } else { MAP_FAILED }, SeqCst);
+                    });
+                recording.map_state = Some(state);
+            }
+        }
+
+        cx.device.poll(wgpu::Maintain::Poll);
+
+        let mut latest = None;
+        while let Some(recording) = self.in_flight.front() {
+            let ready = recording
+                .map_state
+                .as_ref()
+                .map_or(true, |state| state.load(SeqCst) != MAP_PENDING);
+            if !ready {
+                break;
+            }
+            let recording = self.in_flight.pop_front().unwrap();
+            latest = Some(self.finish(recording));
+        }
+        latest
+    }
+
+    fn finish(&mut self, mut recording: Recording) -> ProfilerFrame {
+        let mut durations = recording
+            .scopes
+            .iter()
+            .map(|scope| scope.cpu_end - scope.cpu_begin)
+            .collect::<Vec<_>>();
+        let mut statistics = Vec::new();
+        let mut gpu_timing = false;
+
+        if let Some(queries) = recording.queries.take() {
+            let mapped = recording
+                .map_state
+                .as_ref()
+                .is_some_and(|state| state.load(SeqCst) == MAP_DONE);
+            if mapped {
+                {
+                    let data = queries.read.slice(..).get_mapped_range();
+                    let read = |offset: usize| {
+                        u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+                    };
+
+                    if queries.timestamps.is_some() {
+                        gpu_timing = true;
+                        for (scope, duration) in recording.scopes.iter().zip(&mut durations) {
+                            if let Some(query) = scope.query {
+                                let at = query as usize * 16;
+                                let ticks = read(at + 8).saturating_sub(read(at));
+                                *duration = Duration::from_nanos(
+                                    (ticks as f64 * self.period as f64) as u64,
+                                );
+                            }
+                        }
+                    }
+
+                    let offset = self.statistics_offset() as usize;
+                    for (i, label) in recording.statistics.drain(..).enumerate() {
+                        let at = offset + i * STATISTICS_SIZE as usize;
+                        statistics.push((
+                            label,
+                            PipelineStatistics {
+                                vertex_shader_invocations: read(at),
+                                clipper_invocations: read(at + 8),
+                                clipper_primitives_out: read(at + 16),
+                                fragment_shader_invocations: read(at + 24),
+                            },
+                        ));
+                    }
+                }
+                queries.read.unmap();
+            }
+            self.pool.push(queries);
+        }
+
+        ProfilerFrame {
+            scopes: build_tree(&recording.scopes, &durations, None),
+            statistics,
+            gpu_timing,
+        }
+    }
+
+    fn statistics_offset(&self) -> u64 {
+        let size = self.max_scopes as u64 * 2 * wgpu::QUERY_SIZE as u64;
+        let align = wgpu::QUERY_RESOLVE_BUFFER_ALIGNMENT;
+        size.div_ceil(align) * align
+    }
+
+    fn create_queries(&self, cx: &Context) -> QueryBuffers {
+        let timestamps = self.timestamps.then(|| {
+            Arc::new(cx.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("e2 profiler timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: self.max_scopes * 2,
+            }))
+        });
+        let statistics = self.statistics.then(|| {
+            Arc::new(cx.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("e2 profiler statistics"),
+                ty: wgpu::QueryType::PipelineStatistics(STATISTICS_TYPES),
+                count: self.max_scopes,
+            }))
+        });
+
+        let size = self.statistics_offset() + self.max_scopes as u64 * STATISTICS_SIZE;
+        let read = cx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("e2 profiler readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        QueryBuffers {
+            timestamps,
+            statistics,
+            read,
+        }
+    }
+}
+
+fn build_tree(
+    scopes: &[RawScope],
+    durations: &[Duration],
+    parent: Option<usize>,
+) -> Vec<ProfilerScope> {
+    scopes
+        .iter()
+        .enumerate()
+        .filter(|(_, scope)| scope.parent == parent)
+        .map(|(i, scope)| ProfilerScope {
+            label: scope.label.clone(),
+            duration: durations[i],
+            children: build_tree(scopes, durations, Some(i)),
+        })
+        .collect()
+}
@@ -0,0 +1,650 @@
+use crate::*;
+use std::num::{NonZeroU32, NonZeroU64};
+
+/// Resource binding of a shader, reflected by [ShaderReflection].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectedBinding {
+    pub group: u32,
+    pub binding: u32,
+    /// Name of the global variable.
+    pub name: Option<String>,
+    /// Layout entry matching the binding, visible to the stages of the entry points using it.
+    ///
+    /// Buffers never use dynamic offsets, since the shader does not determine them.
+    /// Float textures are assumed filterable and samplers filtering, since WGSL does not distinguish them;
+    /// replace them with non-filterable entries where needed.
+    pub entry: LayoutEntry,
+    /// Size in bytes of a buffer binding.
+    /// Runtime-sized arrays are counted as a single element.
+    pub size: Option<u64>,
+}
+
+/// Vertex input of a shader entry point, reflected by [ShaderReflection].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReflectedVertexInput {
+    /// `@location` of the input.
+    pub location: u32,
+    pub format: wgpu::VertexFormat,
+}
+
+/// Entry point of a shader, reflected by [ShaderReflection].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectedEntryPoint {
+    pub name: String,
+    pub stage: wgpu::ShaderStages,
+    /// Vertex inputs sorted by location; empty for non-vertex entry points.
+    pub vertex_inputs: Vec<ReflectedVertexInput>,
+}
+
+/// Bindings and entry points of a WGSL shader, reflected with naga.
+///
+/// Usually obtained from [ProcessedShader::reflect] or [ShaderReflection::from_wgsl].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderReflection {
+    /// Bindings sorted by group and binding.
+    pub bindings: Vec<ReflectedBinding>,
+    pub entry_points: Vec<ReflectedEntryPoint>,
+    /// Push constant range covering the `var<push_constant>` of the shader, if any.
+    pub push_constants: Option<wgpu::PushConstantRange>,
+}
+
+impl ShaderReflection {
+    /// Reflects a validated naga module.
+    pub fn new(module: &naga::Module, info: &naga::valid::ModuleInfo) -> Result<Self> {
+        let mut bindings = vec![];
+        let mut push_constants = None;
+        for (handle, global) in module.global_variables.iter() {
+            let visible = module
+                .entry_points
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| !info.get_entry_point(i)[handle].is_empty())
+                .fold(wgpu::ShaderStages::NONE, |visible, (_, entry_point)| {
+                    visible | stage(entry_point.stage)
+                });
+
+            if global.space == naga::AddressSpace::PushConstant {
+                push_constants = Some(wgpu::PushConstantRange {
+                    stages: visible,
+                    range: 0..module.types[global.ty].inner.size(&module.constants),
+                });
+                continue;
+            }
+
+            let binding = match &global.binding {
+                Some(binding) => binding,
+                None => continue,
+            };
+
+            let name = global.name.clone();
+            let (entry, size) = layout_entry(module, global, visible).map_err(|message| {
+                Error::LayoutMismatch(format!(
+                    "binding {} of group {} ({}): {}",
+                    binding.binding,
+                    binding.group,
+                    name.as_deref().unwrap_or("unnamed"),
+                    message
+                ))
+            })?;
+
+            bindings.push(ReflectedBinding {
+                group: binding.group,
+                binding: binding.binding,
+                name,
+                entry,
+                size,
+            });
+        }
+        bindings.sort_by_key(|binding| (binding.group, binding.binding));
+
+        let entry_points = module
+            .entry_points
+            .iter()
+            .map(|entry_point| {
+                let mut vertex_inputs = vec![];
+                if entry_point.stage == naga::ShaderStage::Vertex {
+                    for argument in &entry_point.function.arguments {
+                        vertex_input(module, argument.ty, &argument.binding, &mut vertex_inputs)
+                            .map_err(|message| {
+                                Error::LayoutMismatch(format!(
+                                    "entry point {}: {}",
+                                    entry_point.name, message
+                                ))
+                            })?;
+                    }
+                }
+                vertex_inputs.sort_by_key(|input| input.location);
+
+                Ok(ReflectedEntryPoint {
+                    name: entry_point.name.clone(),
+                    stage: stage(entry_point.stage),
+                    vertex_inputs,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(ShaderReflection {
+            bindings,
+            entry_points,
+            push_constants,
+        })
+    }
+
+    /// Preprocesses, validates and reflects WGSL `source`.
+    pub fn from_wgsl(source: &str) -> Result<Self> {
+        ShaderPreprocessor::new()
+            .process("shader", source)?
+            .reflect()
+    }
+
+    /// Returns the binding at `group` and `binding`, if any.
+    pub fn binding(&self, group: u32, binding: u32) -> Option<&ReflectedBinding> {
+        self.bindings
+            .iter()
+            .find(|x| (x.group, x.binding) == (group, binding))
+    }
+
+    /// Returns the entry point named `name`, if any.
+    pub fn entry_point(&self, name: &str) -> Option<&ReflectedEntryPoint> {
+        self.entry_points.iter().find(|x| x.name == name)
+    }
+
+    /// Returns the size in bytes of the uniform buffer bound as `name`, if any.
+    pub fn uniform_size(&self, name: &str) -> Option<u64> {
+        self.bindings
+            .iter()
+            .find(|x| {
+                x.name.as_deref() == Some(name)
+                    && matches!(x.entry, LayoutEntry::UniformBuffer { .. })
+            })
+            .and_then(|x| x.size)
+    }
+
+    /// Returns the layout entries of every bind group up to the last one used, for use with [BindGroupLayout].
+    ///
+    /// Groups unused by the shader are empty.
+    /// Fails if the bindings of a group are not contiguous from 0, since [BindGroupLayout] numbers entries by index.
+    pub fn layout_entries(&self) -> Result<Vec<Vec<LayoutEntry>>> {
+        let count = self.bindings.last().map_or(0, |x| x.group as usize + 1);
+        let mut groups = vec![vec![]; count];
+        for binding in &self.bindings {
+            let group: &mut Vec<LayoutEntry> = &mut groups[binding.group as usize];
+            if binding.binding as usize != group.len() {
+                return Err(Error::LayoutMismatch(format!(
+                    "bindings of group {} are not contiguous; binding {} is missing",
+                    binding.group,
+                    group.len()
+                )));
+            }
+            group.push(binding.entry);
+        }
+        Ok(groups)
+    }
+
+    /// Creates a pipeline layout matching the shader, including its push constants.
+    ///
+    /// Also returns, at tuple index 1, the [wgpu::BindGroupLayout]s created in the process.
+    /// See [ShaderReflection::layout_entries].
+    pub fn create_layout(
+        &self,
+        cx: &Context,
+    ) -> Result<(wgpu::PipelineLayout, Vec<wgpu::BindGroupLayout>)> {
+        let entries = self.layout_entries()?;
+        let groups = entries
+            .iter()
+            .map(|entries| BindGroupLayout(entries))
+            .collect::<Vec<_>>();
+        Ok(PipelineLayout(&groups).create_with_push_constants(cx, self.push_constants.as_slice()))
+    }
+
+    /// Checks that `layouts` provide every `@location` input of the vertex entry point `entry`, with a matching format.
+    ///
+    /// Attributes are assigned locations in order across the layouts, as in [SimpleRenderPipeline::vertex_layouts].
+    pub fn check_vertex_layouts(&self, entry: &str, layouts: &[VertexLayout]) -> Result<()> {
+        let entry_point = self
+            .entry_point(entry)
+            .ok_or_else(|| Error::LayoutMismatch(format!("no entry point named {}", entry)))?;
+
+        for input in &entry_point.vertex_inputs {
+            let format = layouts
+                .iter()
+                .flat_map(|layout| layout.attributes)
+                .nth(input.location as usize)
+                .map(|&attr| wgpu::VertexFormat::from(attr));
+            match format {
+                Some(format) if format == input.format => {}
+                Some(format) => {
+                    return Err(Error::LayoutMismatch(format!(
+                        "@location({}) of {} is {:?}, but the vertex layouts provide {:?}",
+                        input.location, entry, input.format, format
+                    )))
+                }
+                None => {
+                    return Err(Error::LayoutMismatch(format!(
+                        "@location({}) of {} is not provided by the vertex layouts",
+                        input.location, entry
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stage(stage: naga::ShaderStage) -> wgpu::ShaderStages {
+    match stage {
+        naga::ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+        naga::ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+        naga::ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+    }
+}
+
+fn layout_entry(
+    module: &naga::Module,
+    global: &naga::GlobalVariable,
+    visible: wgpu::ShaderStages,
+) -> std::result::Result<(LayoutEntry, Option<u64>), String> {
+    let (ty, count) = match module.types[global.ty].inner {
+        naga::TypeInner::BindingArray { base, size } => (base, Some(array_count(module, size)?)),
+        _ => (global.ty, None),
+    };
+    let inner = &module.types[ty].inner;
+    let size = NonZeroU64::new(inner.size(&module.constants) as _);
+
+    let entry = match (global.space, inner) {
+        (naga::AddressSpace::Uniform, _) => LayoutEntry::UniformBuffer {
+            visible,
+            count,
+            dynamic_offset: false,
+            min_binding_size: size,
+        },
+        (naga::AddressSpace::Storage { access }, _) => LayoutEntry::StorageBuffer {
+            visible,
+            count,
+            dynamic_offset: false,
+            min_binding_size: size,
+            read_only: !access.contains(naga::StorageAccess::STORE),
+        },
+        (naga::AddressSpace::Handle, &naga::TypeInner::Sampler { comparison }) => {
+            LayoutEntry::Sampler {
+                visible,
+                count,
+                comparison,
+            }
+        }
+        (
+            naga::AddressSpace::Handle,
+            &naga::TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            },
+        ) => {
+            let dimension = match (dim, arrayed) {
+                (naga::ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+                (naga::ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+                (naga::ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+                (naga::ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+                (naga::ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+                (naga::ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+            };
+            match class {
+                naga::ImageClass::Sampled { kind, multi } => LayoutEntry::Texture {
+                    visible,
+                    count,
+                    ty: match kind {
+                        naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                        naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                        _ => wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    dimension,
+                    multisampled: multi,
+                },
+                naga::ImageClass::Depth { multi } => LayoutEntry::DepthTexture {
+                    visible,
+                    count,
+                    dimension,
+                    multisampled: multi,
+                },
+                naga::ImageClass::Storage { format, access } => LayoutEntry::StorageTexture {
+                    visible,
+                    count,
+                    access: if access
+                        .contains(naga::StorageAccess::LOAD | naga::StorageAccess::STORE)
+                    {
+                        wgpu::StorageTextureAccess::ReadWrite
+                    } else if access.contains(naga::StorageAccess::STORE) {
+                        wgpu::StorageTextureAccess::WriteOnly
+                    } else {
+                        wgpu::StorageTextureAccess::ReadOnly
+                    },
+                    format: storage_format(format),
+                    dimension,
+                },
+            }
+        }
+        (space, _) => return Err(format!("unsupported binding in {:?} space", space)),
+    };
+
+    let size = match entry {
+        LayoutEntry::UniformBuffer { .. } | LayoutEntry::StorageBuffer { .. } => {
+            size.map(NonZeroU64::get)
+        }
+        _ => None,
+    };
+    Ok((entry, size))
+}
+
+fn storage_format(format: naga::StorageFormat) -> wgpu::TextureFormat {
+    use naga::StorageFormat as S;
+    use wgpu::TextureFormat as T;
+    match format {
+        S::R8Unorm => T::R8Unorm,
+        S::R8Snorm => T::R8Snorm,
+        S::R8Uint => T::R8Uint,
+        S::R8Sint => T::R8Sint,
+        S::R16Uint => T::R16Uint,
+        S::R16Sint => T::R16Sint,
+        S::R16Float => T::R16Float,
+        S::Rg8Unorm => T::Rg8Unorm,
+        S::Rg8Snorm => T::Rg8Snorm,
+        S::Rg8Uint => T::Rg8Uint,
+        S::Rg8Sint => T::Rg8Sint,
+        S::R32Uint => T::R32Uint,
+        S::R32Sint => T::R32Sint,
+        S::R32Float => T::R32Float,
+        S::Rg16Uint => T::Rg16Uint,
+        S::Rg16Sint => T::Rg16Sint,
+        S::Rg16Float => T::Rg16Float,
+        S::Rgba8Unorm => T::Rgba8Unorm,
+        S::Rgba8Snorm => T::Rgba8Snorm,
+        S::Rgba8Uint => T::Rgba8Uint,
+        S::Rgba8Sint => T::Rgba8Sint,
+        S::Rgb10a2Unorm => T::Rgb10a2Unorm,
+        S::Rg11b10Float => T::Rg11b10Float,
+        S::Rg32Uint => T::Rg32Uint,
+        S::Rg32Sint => T::Rg32Sint,
+        S::Rg32Float => T::Rg32Float,
+        S::Rgba16Uint => T::Rgba16Uint,
+        S::Rgba16Sint => T::Rgba16Sint,
+        S::Rgba16Float => T::Rgba16Float,
+        S::Rgba32Uint => T::Rgba32Uint,
+        S::Rgba32Sint => T::Rgba32Sint,
+        S::Rgba32Float => T::Rgba32Float,
+    }
+}
+
+fn array_count(
+    module: &naga::Module,
+    size: naga::ArraySize,
+) -> std::result::Result<NonZeroU32, String> {
+    let count = match size {
+        naga::ArraySize::Constant(handle) => match module.constants[handle].inner {
+            naga::ConstantInner::Scalar {
+                value: naga::ScalarValue::Uint(count),
+                ..
+            } => count.try_into().ok(),
+            naga::ConstantInner::Scalar {
+                value: naga::ScalarValue::Sint(count),
+                ..
+            } => count.try_into().ok(),
+            _ => None,
+        },
+        naga::ArraySize::Dynamic => None,
+    };
+    count
+        .and_then(NonZeroU32::new)
+        .ok_or_else(|| "binding arrays must have a constant size".into())
+}
+
+fn vertex_input(
+    module: &naga::Module,
+    ty: naga::Handle<naga::Type>,
+    binding: &Option<naga::Binding>,
+    inputs: &mut Vec<ReflectedVertexInput>,
+) -> std::result::Result<(), String> {
+    let inner = &module.types[ty].inner;
+    match (binding, inner) {
+        (Some(naga::Binding::Location { location, .. }), _) => {
+            let format = vertex_format(inner)
+                .ok_or_else(|| format!("unsupported type of @location({})", location))?;
+            inputs.push(ReflectedVertexInput {
+                location: *location,
+                format,
+            });
+        }
+        (None, naga::TypeInner::Struct { members, .. }) => {
+            for member in members {
+                vertex_input(module, member.ty, &member.binding, inputs)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn vertex_format(inner: &naga::TypeInner) -> Option<wgpu::VertexFormat> {
+    use wgpu::VertexFormat::*;
+
+    let (kind, size) = match *inner {
+        naga::TypeInner::Scalar { kind, width: 4 } => (kind, 1),
+        naga::TypeInner::Vector {
+            size,
+            kind,
+            width: 4,
+        } => (kind, size as u8),
+        _ => return None,
+    };
+
+    Some(match (kind, size) {
+        (naga::ScalarKind::Float, 1) => Float32,
+        (naga::ScalarKind::Float, 2) => Float32x2,
+        (naga::ScalarKind::Float, 3) => Float32x3,
+        (naga::ScalarKind::Float, 4) => Float32x4,
+        (naga::ScalarKind::Sint, 1) => Sint32,
+        (naga::ScalarKind::Sint, 2) => Sint32x2,
+        (naga::ScalarKind::Sint, 3) => Sint32x3,
+        (naga::ScalarKind::Sint, 4) => Sint32x4,
+        (naga::ScalarKind::Uint, 1) => Uint32,
+        (naga::ScalarKind::Uint, 2) => Uint32x2,
+        (naga::ScalarKind::Uint, 3) => Uint32x3,
+        (naga::ScalarKind::Uint, 4) => Uint32x4,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADER: &str = "
+struct Params { color: vec4<f32>, scale: f32 }
+struct Instance { offset: vec2<f32> }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> instances: array<Instance>;
+@group(2) @binding(0) var tex: texture_2d<f32>;
+@group(2) @binding(1) var samp: sampler;
+@group(2) @binding(2) var shadow: texture_depth_2d;
+@group(2) @binding(3) var shadow_sampler: sampler_comparison;
+@group(2) @binding(4) var output: texture_storage_2d<rgba8unorm, write>;
+
+struct VertexInput { @location(0) pos: vec2<f32>, @location(1) uv: vec2<f32> }
+struct VertexOutput { @builtin(position) pos: vec4<f32>, @location(0) uv: vec2<f32> }
+
+@vertex
+fn vs_main(in: VertexInput, @location(2) color: vec4<f32>, @builtin(instance_index) i: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.pos = vec4<f32>(in.pos * params.scale + instances[i].offset, 0., 1.);
+    out.uv = in.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let depth = textureSampleCompare(shadow, shadow_sampler, in.uv, 0.5);
+    textureStore(output, vec2<i32>(0, 0), vec4<f32>(depth));
+    return textureSample(tex, samp, in.uv) * params.color;
+}
+";
+
+    #[test]
+    fn bindings() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+        let both = wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT;
+        let fragment = wgpu::ShaderStages::FRAGMENT;
+
+        let params = reflection.binding(0, 0).unwrap();
+        assert_eq!(params.name.as_deref(), Some("params"));
+        assert_eq!(
+            params.entry,
+            LayoutEntry::UniformBuffer {
+                visible: both,
+                count: None,
+                dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(32),
+            }
+        );
+        assert_eq!(reflection.uniform_size("params"), Some(32));
+        assert_eq!(reflection.uniform_size("instances"), None);
+
+        assert_eq!(
+            reflection.binding(0, 1).unwrap().entry,
+            LayoutEntry::StorageBuffer {
+                visible: wgpu::ShaderStages::VERTEX,
+                count: None,
+                dynamic_offset: false,
+                min_binding_size: NonZeroU64::new(8),
+                read_only: true,
+            }
+        );
+        assert_eq!(
+            reflection.binding(2, 0).unwrap().entry,
+            LayoutEntry::Texture {
+                visible: fragment,
+                count: None,
+                ty: wgpu::TextureSampleType::Float { filterable: true },
+                dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            }
+        );
+        assert_eq!(
+            reflection.binding(2, 1).unwrap().entry,
+            LayoutEntry::Sampler {
+                visible: fragment,
+                count: None,
+                comparison: false,
+            }
+        );
+        assert_eq!(
+            reflection.binding(2, 2).unwrap().entry,
+            LayoutEntry::DepthTexture {
+                visible: fragment,
+                count: None,
+                dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            }
+        );
+        assert_eq!(
+            reflection.binding(2, 3).unwrap().entry,
+            LayoutEntry::Sampler {
+                visible: fragment,
+                count: None,
+                comparison: true,
+            }
+        );
+        assert_eq!(
+            reflection.binding(2, 4).unwrap().entry,
+            LayoutEntry::StorageTexture {
+                visible: fragment,
+                count: None,
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                dimension: wgpu::TextureViewDimension::D2,
+            }
+        );
+        assert_eq!(reflection.push_constants, None);
+    }
+
+    #[test]
+    fn layout_entries() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+        let groups = reflection.layout_entries().unwrap();
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [2, 0, 5]);
+        assert_eq!(groups[2][4], reflection.binding(2, 4).unwrap().entry);
+
+        let reflection = ShaderReflection::from_wgsl(
+            "@group(0) @binding(1) var<uniform> a: vec4<f32>;
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return a; }",
+        )
+        .unwrap();
+        assert!(matches!(
+            reflection.layout_entries(),
+            Err(Error::LayoutMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn vertex_inputs() {
+        let reflection = ShaderReflection::from_wgsl(SHADER).unwrap();
+        let vs = reflection.entry_point("vs_main").unwrap();
+        assert_eq!(vs.stage, wgpu::ShaderStages::VERTEX);
+        assert_eq!(
+            vs.vertex_inputs,
+            [
+                ReflectedVertexInput {
+                    location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                ReflectedVertexInput {
+                    location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                ReflectedVertexInput {
+                    location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ]
+        );
+        assert!(reflection
+            .entry_point("fs_main")
+            .unwrap()
+            .vertex_inputs
+            .is_empty());
+
+        let colors = VertexLayout {
+            stride: 16,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[VertexAttribute::Vec4 { offset: 0 }],
+        };
+        assert!(reflection
+            .check_vertex_layouts("vs_main", &[Vertex::layout(), colors])
+            .is_ok());
+        assert!(reflection
+            .check_vertex_layouts("vs_main", &[Vertex::layout()])
+            .is_err());
+        assert!(reflection
+            .check_vertex_layouts("vs_main", &[colors, Vertex::layout()])
+            .is_err());
+        assert!(reflection.check_vertex_layouts("missing", &[]).is_err());
+    }
+
+    #[test]
+    fn push_constants() {
+        let reflection = ShaderReflection::from_wgsl(
+            "struct Pc { tint: vec4<f32>, scale: f32 }
+            var<push_constant> pc: Pc;
+            @vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(pc.scale); }
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return pc.tint; }",
+        )
+        .unwrap();
+        assert_eq!(
+            reflection.push_constants,
+            Some(wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                range: 0..32,
+            })
+        );
+        assert!(reflection.bindings.is_empty());
+    }
+}
@@ -0,0 +1,587 @@
+use crate::*;
+use std::collections::{BTreeSet, HashMap};
+
+/// Description of a transient texture, also used as the key of a [TexturePool].
+///
+/// Transient textures can be rendered to, sampled, and copied from and to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureDesc {
+    pub format: wgpu::TextureFormat,
+    pub samples: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Pool of render textures, reused across nodes and frames of a [RenderGraph].
+#[derive(Debug, Default)]
+pub struct TexturePool {
+    free: HashMap<TextureDesc, Vec<Texture>>,
+}
+
+impl TexturePool {
+    /// Creates a new, empty [TexturePool].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a texture matching `desc` from the pool, creating one if there are none free.
+    pub fn acquire(&mut self, cx: &Context, desc: TextureDesc) -> Texture {
+        self.free
+            .get_mut(&desc)
+            .and_then(Vec::pop)
+            .unwrap_or_else(|| {
+                RenderTexture {
+                    label: Some("e2 render graph transient"),
+                    format: desc.format,
+                    samples: desc.samples,
+                    width: desc.width,
+                    height: desc.height,
+                    binding: true,
+                }
+                .create_with_usage(
+                    cx,
+                    wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+                )
+            })
+    }
+
+    /// Returns a texture previously taken with [TexturePool::acquire] to the pool.
+    pub fn release(&mut self, desc: TextureDesc, texture: Texture) {
+        self.free.entry(desc).or_default().push(texture);
+    }
+
+    /// Drops all the free textures.
+    pub fn clear(&mut self) {
+        self.free.clear();
+    }
+}
+
+/// Declaration of a node drawing into an [ArenaRenderPass].
+#[derive(Debug, Clone, Copy)]
+pub struct RenderNode<'n> {
+    /// Debug label of the node, also used as the pass label.
+    pub label: &'n str,
+    /// Textures sampled by the node.
+    pub reads: &'n [&'n str],
+    /// Texture rendered to.
+    pub color: &'n str,
+    /// Texture to resolve `color` into, if multisampled.
+    pub resolve: Option<&'n str>,
+    /// Depth texture.
+    pub depth: Option<&'n str>,
+    /// Clear color; if `None` then `color` is loaded.
+    pub clear: Option<Color>,
+    /// Depth clear value; if `None` then `depth` is loaded.
+    pub depth_clear: Option<f32>,
+    /// Depth comparison of the pipelines drawing into `depth`, e.g. `Greater` for a reversed depth buffer.
+    pub depth_compare: wgpu::CompareFunction,
+    /// Whether the pipelines drawing into `depth` write to it.
+    pub depth_write: bool,
+}
+
+/// Declaration of a node recording directly into the frame command encoder.
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderNode<'n> {
+    /// Debug label of the node.
+    pub label: &'n str,
+    /// Textures read by the node.
+    pub reads: &'n [&'n str],
+    /// Textures written by the node.
+    pub writes: &'n [&'n str],
+}
+
+type RenderFn<'a> = Box<dyn for<'p> FnOnce(&mut ArenaRenderPass<'p>, &'p GraphResources<'a>) + 'a>;
+type EncoderFn<'a> = Box<dyn FnOnce(&mut Frame, &GraphResources<'a>) + 'a>;
+
+enum NodeKind<'a> {
+    Render {
+        color: String,
+        resolve: Option<String>,
+        depth: Option<String>,
+        clear: Option<Color>,
+        depth_clear: Option<f32>,
+        depth_compare: wgpu::CompareFunction,
+        depth_write: bool,
+        f: RenderFn<'a>,
+    },
+    Encoder(EncoderFn<'a>),
+}
+
+struct Node<'a> {
+    label: String,
+    reads: Vec<String>,
+    writes: Vec<String>,
+    kind: NodeKind<'a>,
+}
+
+/// Textures available to the nodes of a [RenderGraph], by name.
+#[derive(Debug, Default)]
+pub struct GraphResources<'a> {
+    textures: HashMap<String, Texture>,
+    views: HashMap<String, &'a wgpu::TextureView>,
+}
+
+impl<'a> GraphResources<'a> {
+    /// Returns the texture named `name`, if it is currently allocated.
+    ///
+    /// Textures imported with [RenderGraph::import_view] are only available through [GraphResources::view].
+    pub fn get(&self, name: &str) -> Option<&Texture> {
+        self.textures.get(name)
+    }
+
+    /// Returns the texture named `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such texture; see [GraphResources::get].
+    pub fn texture(&self, name: &str) -> &Texture {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no render graph texture named {:?}", name))
+    }
+
+    /// Returns the view of the texture named `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such texture.
+    pub fn view(&self, name: &str) -> &wgpu::TextureView {
+        match self.views.get(name) {
+            Some(view) => view,
+            None => &self.texture(name).view,
+        }
+    }
+}
+
+/// Lightweight render graph which orders passes by their dependencies and manages transient textures.
+///
+/// Nodes declare the named textures they read and write.
+/// Nodes writing the same texture run in the order they were added.
+/// A node that reads a texture sees the contents written by the last node writing it that was added before it,
+/// so it runs after that node and before the next one writing it.
+///
+/// Transient textures are taken from a [TexturePool] just before their first use
+/// and returned right after their last use, so later transients with the same [TextureDesc] alias them.
+pub struct RenderGraph<'a> {
+    nodes: Vec<Node<'a>>,
+    transients: HashMap<String, TextureDesc>,
+    // format and sample count of each texture, where known
+    formats: HashMap<String, (wgpu::TextureFormat, u32)>,
+    resources: GraphResources<'a>,
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Creates a new, empty [RenderGraph].
+    pub fn new() -> Self {
+        RenderGraph {
+            nodes: Vec::new(),
+            transients: HashMap::new(),
+            formats: HashMap::new(),
+            resources: GraphResources::default(),
+        }
+    }
+
+    /// Declares a transient texture, which only exists during [RenderGraph::execute].
+    pub fn transient(&mut self, name: &str, desc: TextureDesc) {
+        self.formats
+            .insert(name.to_owned(), (desc.format, desc.samples));
+        self.transients.insert(name.to_owned(), desc);
+    }
+
+    /// Imports an external texture, e.g. the color texture of a [RenderTarget].
+    ///
+    /// Render nodes can only draw into the texture if its [TextureInfo] is known.
+    pub fn import(&mut self, name: &str, texture: &Texture) {
+        match texture.info() {
+            Some(info) => self
+                .formats
+                .insert(name.to_owned(), (info.format, info.samples)),
+            None => self.formats.remove(name),
+        };
+        self.resources
+            .textures
+            .insert(name.to_owned(), texture.clone());
+    }
+
+    /// Imports an external texture view of `format` with `samples` samples, e.g. a swapchain image.
+    pub fn import_view(
+        &mut self,
+        name: &str,
+        view: &'a wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        samples: u32,
+    ) {
+        self.formats.insert(name.to_owned(), (format, samples));
+        self.resources.views.insert(name.to_owned(), view);
+    }
+
+    /// Adds a node which draws into an [ArenaRenderPass] begun from `node`.
+    pub fn add_render_node(
+        &mut self,
+        node: RenderNode,
+        f: impl for<'p> FnOnce(&mut ArenaRenderPass<'p>, &'p GraphResources<'a>) + 'a,
+    ) {
+        let writes = std::iter::once(node.color)
+            .chain(node.resolve)
+            .chain(node.depth)
+            .map(str::to_owned)
+            .collect();
+
+        self.nodes.push(Node {
+            label: node.label.to_owned(),
+            reads: node.reads.iter().map(|&name| name.to_owned()).collect(),
+            writes,
+            kind: NodeKind::Render {
+                color: node.color.to_owned(),
+                resolve: node.resolve.map(str::to_owned),
+                depth: node.depth.map(str::to_owned),
+                clear: node.clear,
+                depth_clear: node.depth_clear,
+                depth_compare: node.depth_compare,
+                depth_write: node.depth_write,
+                f: Box::new(f),
+            },
+        });
+    }
+
+    /// Adds a node which records directly into the [Frame].
+    pub fn add_encoder_node(
+        &mut self,
+        node: EncoderNode,
+        f: impl FnOnce(&mut Frame, &GraphResources<'a>) + 'a,
+    ) {
+        self.nodes.push(Node {
+            label: node.label.to_owned(),
+            reads: node.reads.iter().map(|&name| name.to_owned()).collect(),
+            writes: node.writes.iter().map(|&name| name.to_owned()).collect(),
+            kind: NodeKind::Encoder(Box::new(f)),
+        });
+    }
+
+    /// Records all the nodes into `frame`, in dependency order.
+    ///
+    /// Transient textures are taken from and returned to `pool`.
+    pub fn execute(self, cx: &Context, frame: &mut Frame, pool: &mut TexturePool) -> Result<()> {
+        let order = self.order()?;
+        let RenderGraph {
+            nodes,
+            transients,
+            formats,
+            mut resources,
+        } = self;
+
+        // position in `order` of the first and last use of each transient
+        let mut lifetimes = HashMap::<&str, (usize, usize)>::new();
+        for (position, &index) in order.iter().enumerate() {
+            let node = &nodes[index];
+            for name in node.reads.iter().chain(&node.writes) {
+                if transients.contains_key(name) {
+                    lifetimes
+                        .entry(name)
+                        .and_modify(|(_, last)| *last = position)
+                        .or_insert((position, position));
+                }
+            }
+        }
+        let lifetimes = lifetimes
+            .into_iter()
+            .map(|(name, lifetime)| (name.to_owned(), lifetime))
+            .collect::<Vec<_>>();
+
+        let mut nodes = nodes.into_iter().map(Some).collect::<Vec<_>>();
+        for (position, index) in order.into_iter().enumerate() {
+            for (name, _) in lifetimes
+                .iter()
+                .filter(|(_, (first, _))| *first == position)
+            {
+                let texture = pool.acquire(cx, transients[name]);
+                resources.textures.insert(name.clone(), texture);
+            }
+
+            let node = nodes[index].take().unwrap();
+            match node.kind {
+                NodeKind::Render {
+                    color,
+                    resolve,
+                    depth,
+                    clear,
+                    depth_clear,
+                    depth_compare,
+                    depth_write,
+                    f,
+                } => {
+                    let format = pass_format(
+                        &formats,
+                        &color,
+                        depth.as_deref(),
+                        depth_compare,
+                        depth_write,
+                    )?;
+                    let mut pass = SimpleRenderPass {
+                        label: Some(&node.label),
+                        target: resources.view(&color),
+                        resolve: resolve.as_deref().map(|name| resources.view(name)),
+                        clear,
+                        extra_targets: &[],
+                        depth_stencil: depth.as_deref().map(|name| {
+                            let stencil = formats
+                                .get(name)
+                                .is_some_and(|&(format, _)| has_stencil(format));
+                            wgpu::RenderPassDepthStencilAttachment {
+                                view: resources.view(name),
+                                depth_ops: Some(wgpu::Operations {
+                                    load: match depth_clear {
+                                        Some(clear) => wgpu::LoadOp::Clear(clear),
+                                        None => wgpu::LoadOp::Load,
+                                    },
+                                    store: true,
+                                }),
+                                stencil_ops: stencil.then_some(wgpu::Operations {
+                                    load: match depth_clear {
+                                        Some(_) => wgpu::LoadOp::Clear(0),
+                                        None => wgpu::LoadOp::Load,
+                                    },
+                                    store: true,
+                                }),
+                            }
+                        }),
+                        format,
+                    }
+                    .begin(frame);
+                    f(&mut pass, &resources);
+                }
+                NodeKind::Encoder(f) => f(frame, &resources),
+            }
+
+            for (name, _) in lifetimes.iter().filter(|(_, (_, last))| *last == position) {
+                if let Some(texture) = resources.textures.remove(name) {
+                    pool.release(transients[name], texture);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Topologically sorts the nodes, preferring the order they were added in.
+    fn order(&self) -> Result<Vec<usize>> {
+        let mut dependencies = vec![BTreeSet::new(); self.nodes.len()];
+        let mut writers = HashMap::<&str, Vec<usize>>::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            for name in node.reads.iter().chain(&node.writes) {
+                if !self.transients.contains_key(name)
+                    && !self.resources.textures.contains_key(name)
+                    && !self.resources.views.contains_key(name)
+                {
+                    return Err(Error::InvalidRenderGraph(format!(
+                        "node {:?} uses undeclared texture {:?}",
+                        node.label, name
+                    )));
+                }
+            }
+            for name in &node.writes {
+                writers.entry(name).or_default().push(index);
+            }
+        }
+
+        // nearest writer of `name` added before the node at `index`
+        let previous_writer = |name: &str, index: usize| {
+            writers
+                .get(name)
+                .and_then(|w| w.iter().take_while(|&&writer| writer < index).last())
+                .copied()
+        };
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            for name in &node.writes {
+                let previous = previous_writer(name, index);
+                dependencies[index].extend(previous);
+
+                // readers of the previous contents must run before they are overwritten
+                let start = previous.map_or(0, |previous| previous + 1);
+                let readers = (start..index).filter(|&other| {
+                    let other = &self.nodes[other];
+                    other.reads.contains(name) && !other.writes.contains(name)
+                });
+                dependencies[index].extend(readers);
+            }
+            for name in node.reads.iter().filter(|name| !node.writes.contains(name)) {
+                dependencies[index].extend(previous_writer(name, index));
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut ready = (0..self.nodes.len())
+            .filter(|&index| dependencies[index].is_empty())
+            .collect::<BTreeSet<_>>();
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+            for (other, deps) in dependencies.iter_mut().enumerate() {
+                if deps.remove(&index) && deps.is_empty() {
+                    ready.insert(other);
+                }
+            }
+        }
+
+        if order.len() != self.nodes.len() {
+            return Err(Error::InvalidRenderGraph(
+                "cyclic dependency between nodes".into(),
+            ));
+        }
+        Ok(order)
+    }
+}
+
+impl<'a> Default for RenderGraph<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the attachment configuration of a render node.
+fn pass_format(
+    formats: &HashMap<String, (wgpu::TextureFormat, u32)>,
+    color: &str,
+    depth: Option<&str>,
+    depth_compare: wgpu::CompareFunction,
+    depth_write: bool,
+) -> Result<PassFormat> {
+    let format_of = |name: &str| {
+        formats.get(name).copied().ok_or_else(|| {
+            Error::InvalidRenderGraph(format!(
+                "format of texture {:?} is unknown; import it with RenderGraph::import_view",
+                name
+            ))
+        })
+    };
+    let (format, samples) = format_of(color)?;
+    let format = PassFormat {
+        format,
+        samples,
+        depth_stencil: None,
+    };
+    Ok(match depth {
+        Some(depth) => format.with_depth(format_of(depth)?.0, depth_compare, depth_write),
+        None => format,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(
+        nodes: &[(
+            &'static str,
+            &'static [&'static str],
+            &'static [&'static str],
+        )],
+    ) -> Vec<String> {
+        let mut graph = RenderGraph::new();
+        for name in ["a", "b", "c"] {
+            graph.transient(
+                name,
+                TextureDesc {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    samples: 1,
+                    width: 1,
+                    height: 1,
+                },
+            );
+        }
+        for &(label, reads, writes) in nodes {
+            graph.add_encoder_node(
+                EncoderNode {
+                    label,
+                    reads,
+                    writes,
+                },
+                |_, _| {},
+            );
+        }
+        let order = graph.order().unwrap();
+        order
+            .into_iter()
+            .map(|index| graph.nodes[index].label.clone())
+            .collect()
+    }
+
+    #[test]
+    fn order_read_after_write() {
+        let order = graph(&[("read", &["a"], &["b"]), ("write", &[], &["a"])]);
+        assert_eq!(order, ["read", "write"]);
+
+        let order = graph(&[
+            ("blur", &["a"], &["b"]),
+            ("scene", &[], &["a"]),
+            ("composite", &["b"], &["c"]),
+        ]);
+        assert_eq!(order, ["blur", "scene", "composite"]);
+    }
+
+    #[test]
+    fn order_write_read_write() {
+        let order = graph(&[
+            ("write 1", &[], &["a"]),
+            ("read 1", &["a"], &["b"]),
+            ("write 2", &[], &["a"]),
+            ("read 2", &["a"], &["c"]),
+        ]);
+        assert_eq!(order, ["write 1", "read 1", "write 2", "read 2"]);
+
+        // the second write waits for the reader of the first, even if it was added later
+        let order = graph(&[
+            ("write 1", &[], &["a"]),
+            ("unrelated", &[], &["c"]),
+            ("read 1", &["c", "a"], &["b"]),
+            ("write 2", &[], &["a"]),
+        ]);
+        assert_eq!(order, ["write 1", "unrelated", "read 1", "write 2"]);
+    }
+
+    #[test]
+    fn order_undeclared_texture() {
+        let mut graph = RenderGraph::new();
+        graph.add_encoder_node(
+            EncoderNode {
+                label: "node",
+                reads: &["missing"],
+                writes: &[],
+            },
+            |_, _| {},
+        );
+        assert!(matches!(graph.order(), Err(Error::InvalidRenderGraph(_))));
+    }
+
+    #[test]
+    fn pass_format_of_textures() {
+        let mut formats = HashMap::new();
+        formats.insert("color".to_owned(), (wgpu::TextureFormat::Rgba8Unorm, 4));
+        formats.insert("depth".to_owned(), (wgpu::TextureFormat::Depth32Float, 4));
+
+        let format = pass_format(
+            &formats,
+            "color",
+            Some("depth"),
+            wgpu::CompareFunction::Greater,
+            false,
+        )
+        .unwrap();
+        assert_eq!(format.format, wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!(format.samples, 4);
+        let depth = format.depth_stencil.unwrap();
+        assert_eq!(depth.format, wgpu::TextureFormat::Depth32Float);
+        assert_eq!(depth.depth_compare, wgpu::CompareFunction::Greater);
+        assert!(!depth.depth_write_enabled);
+
+        assert!(matches!(
+            pass_format(
+                &formats,
+                "color",
+                Some("unknown"),
+                wgpu::CompareFunction::Less,
+                true
+            ),
+            Err(Error::InvalidRenderGraph(_))
+        ));
+    }
+}
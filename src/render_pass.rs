@@ -4,6 +4,8 @@ use std::sync::Arc;
 /// Simplified render pass descriptor.
 #[derive(Debug, Clone)]
 pub struct SimpleRenderPass<'a> {
+    /// Debug label of the pass.
+    pub label: Option<&'a str>,
     /// Texture to render to.
     pub target: &'a wgpu::TextureView,
     /// Texture to resolve samples from `target` into.
@@ -12,32 +14,47 @@ pub struct SimpleRenderPass<'a> {
     /// Clear color.
     /// If `None` then the texture is not cleared.
     pub clear: Option<Color>,
+    /// Color attachments following `target`, for pipelines with multiple color targets
+    /// (see [SimpleRenderPipeline::extra_targets]).
+    ///
+    /// The pipelines of the built-in renderers have a single color target, so they cannot draw into such passes.
+    pub extra_targets: &'a [wgpu::RenderPassColorAttachment<'a>],
     /// Depth texture.
     pub depth_stencil: Option<wgpu::RenderPassDepthStencilAttachment<'a>>,
+    /// Attachment configuration, used by renderers to select matching pipelines from [Context::pipelines].
+    ///
+    /// Texture views do not carry their format, so it must be given to match `target` and `depth_stencil`.
+    pub format: PassFormat,
 }
 
 impl<'a> SimpleRenderPass<'a> {
     /// Begins a new [ArenaRenderPass] from the stored pass configuration.
     pub fn begin(self, frame: &'a mut Frame) -> ArenaRenderPass<'a> {
-        let pass = frame.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: self.target,
-                resolve_target: self.resolve,
-                ops: wgpu::Operations {
-                    load: match self.clear {
-                        Some(color) => wgpu::LoadOp::Clear(color.into()),
-                        None => wgpu::LoadOp::Load,
-                    },
-                    store: true,
+        let color_attachments = std::iter::once(Some(wgpu::RenderPassColorAttachment {
+            view: self.target,
+            resolve_target: self.resolve,
+            ops: wgpu::Operations {
+                load: match self.clear {
+                    Some(color) => wgpu::LoadOp::Clear(color.into()),
+                    None => wgpu::LoadOp::Load,
                 },
-            })],
+                store: true,
+            },
+        }))
+        .chain(self.extra_targets.iter().cloned().map(Some))
+        .collect::<Vec<_>>();
+
+        let pass = frame.cmd.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: self.label,
+            color_attachments: &color_attachments,
             depth_stencil_attachment: self.depth_stencil,
         });
 
         ArenaRenderPass {
             arena: &frame.arena,
             pass,
+            format: self.format,
+            pipeline: None,
         }
     }
 }
@@ -48,6 +65,9 @@ impl<'a> SimpleRenderPass<'a> {
 pub struct ArenaRenderPass<'a> {
     pub arena: &'a FrameArena,
     pub pass: wgpu::RenderPass<'a>,
+    /// Attachment configuration of the pass. See [SimpleRenderPass::format].
+    pub format: PassFormat,
+    pipeline: Option<&'a wgpu::RenderPipeline>,
 }
 
 impl<'a> ArenaRenderPass<'a> {
@@ -55,6 +75,15 @@ impl<'a> ArenaRenderPass<'a> {
     pub fn set_pipeline(&mut self, pipeline: Arc<wgpu::RenderPipeline>) {
         let pipeline = self.arena.render_pipelines.alloc(pipeline);
         self.pass.set_pipeline(pipeline);
+        self.pipeline = Some(pipeline);
+    }
+
+    /// Returns `true` if `pipeline` was the last pipeline set with [ArenaRenderPass::set_pipeline].
+    ///
+    /// Pipelines set directly on [ArenaRenderPass::pass] are not tracked.
+    pub fn is_pipeline_bound(&self, pipeline: &wgpu::RenderPipeline) -> bool {
+        self.pipeline
+            .is_some_and(|bound| std::ptr::eq(bound, pipeline))
     }
 
     /// See [wgpu::RenderPass::set_bind_group].
@@ -90,6 +119,46 @@ impl<'a> ArenaRenderPass<'a> {
         self.pass
             .set_index_buffer(buffer.slice(offset..), index_format);
     }
+
+    /// See [wgpu::RenderPass::push_debug_group].
+    pub fn push_debug_group(&mut self, label: &str) {
+        self.pass.push_debug_group(label);
+    }
+
+    /// See [wgpu::RenderPass::pop_debug_group].
+    pub fn pop_debug_group(&mut self) {
+        self.pass.pop_debug_group();
+    }
+
+    /// See [wgpu::RenderPass::insert_debug_marker].
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        self.pass.insert_debug_marker(label);
+    }
+
+    /// Runs `f` within a debug group named `label`.
+    pub fn debug_group<R>(&mut self, label: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.push_debug_group(label);
+        let result = f(self);
+        self.pop_debug_group();
+        result
+    }
+
+    /// See [wgpu::RenderPass::write_timestamp].
+    pub fn write_timestamp(&mut self, query_set: Arc<wgpu::QuerySet>, query_index: u32) {
+        let query_set = self.arena.query_sets.alloc(query_set);
+        self.pass.write_timestamp(query_set, query_index);
+    }
+
+    /// See [wgpu::RenderPass::begin_pipeline_statistics_query].
+    pub fn begin_pipeline_statistics_query(
+        &mut self,
+        query_set: Arc<wgpu::QuerySet>,
+        query_index: u32,
+    ) {
+        let query_set = self.arena.query_sets.alloc(query_set);
+        self.pass
+            .begin_pipeline_statistics_query(query_set, query_index);
+    }
 }
 
 impl<'a> std::ops::Deref for ArenaRenderPass<'a> {
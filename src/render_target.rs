@@ -0,0 +1,229 @@
+use crate::*;
+
+/// Offscreen render target owning a sampleable color texture,
+/// an optional depth texture and an optional multisampled color buffer.
+///
+/// When multisampled, passes render into the multisampled buffer and are
+/// automatically resolved into [RenderTarget::color].
+#[derive(Debug)]
+pub struct RenderTarget {
+    /// Single-sampled color texture; this is the output of the target.
+    pub color: Texture,
+    /// Multisampled color buffer, present when `samples > 1`.
+    pub msaa: Option<Texture>,
+    /// Depth (and possibly stencil) texture.
+    pub depth: Option<Texture>,
+    /// Value the depth texture is cleared to by [RenderTarget::begin_pass].
+    pub depth_clear: f32,
+    /// Depth comparison used by the built-in pipelines selected for passes of this target.
+    pub depth_compare: wgpu::CompareFunction,
+    /// Whether the built-in pipelines selected for passes of this target write to the depth texture.
+    pub depth_write: bool,
+    format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+    samples: u32,
+    width: u32,
+    height: u32,
+}
+
+impl RenderTarget {
+    /// Creates a new [RenderTarget] with color `format`, sample count and size in pixels.
+    ///
+    /// A depth texture is created if `depth_format` is `Some`.
+    pub fn new(
+        cx: &Context,
+        format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        samples: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let (color, msaa, depth) =
+            Self::create_textures(cx, format, depth_format, samples, width, height);
+        RenderTarget {
+            color,
+            msaa,
+            depth,
+            depth_clear: 1.,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            depth_write: true,
+            format,
+            depth_format,
+            samples,
+            width,
+            height,
+        }
+    }
+
+    /// Recreates the textures with a new size.
+    ///
+    /// Does nothing if the size is unchanged.
+    /// Note that this changes the [Texture::id] of all the textures.
+    pub fn resize(&mut self, cx: &Context, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        let (color, msaa, depth) = Self::create_textures(
+            cx,
+            self.format,
+            self.depth_format,
+            self.samples,
+            width,
+            height,
+        );
+        self.color = color;
+        self.msaa = msaa;
+        self.depth = depth;
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Begins a new [ArenaRenderPass] rendering into this target.
+    ///
+    /// If `clear` is `Some`, the color is cleared to it and the depth is cleared to [RenderTarget::depth_clear].
+    /// Otherwise the existing contents are kept.
+    pub fn begin_pass<'a>(
+        &'a self,
+        frame: &'a mut Frame,
+        clear: Option<Color>,
+    ) -> ArenaRenderPass<'a> {
+        let (target, resolve) = match &self.msaa {
+            Some(msaa) => (msaa.view.as_ref(), Some(self.color.view.as_ref())),
+            None => (self.color.view.as_ref(), None),
+        };
+
+        SimpleRenderPass {
+            label: Some("e2 render target pass"),
+            target,
+            resolve,
+            clear,
+            extra_targets: &[],
+            depth_stencil: self.depth.as_ref().map(|depth| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: match clear {
+                            Some(_) => wgpu::LoadOp::Clear(self.depth_clear),
+                            None => wgpu::LoadOp::Load,
+                        },
+                        store: true,
+                    }),
+                    stencil_ops: self.depth_format.filter(|&format| has_stencil(format)).map(
+                        |_| wgpu::Operations {
+                            load: match clear {
+                                Some(_) => wgpu::LoadOp::Clear(0),
+                                None => wgpu::LoadOp::Load,
+                            },
+                            store: true,
+                        },
+                    ),
+                }
+            }),
+            format: self.pass_format(),
+        }
+        .begin(frame)
+    }
+
+    /// Returns the attachment configuration of passes begun by [RenderTarget::begin_pass].
+    pub fn pass_format(&self) -> PassFormat {
+        let format = PassFormat {
+            format: self.format,
+            samples: self.samples,
+            depth_stencil: None,
+        };
+        match self.depth_format {
+            Some(depth_format) => {
+                format.with_depth(depth_format, self.depth_compare, self.depth_write)
+            }
+            None => format,
+        }
+    }
+
+    /// Returns the color texture format.
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// Returns the depth texture format, if any.
+    #[inline]
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_format
+    }
+
+    /// Returns the sample count that pipelines rendering into this target must use.
+    #[inline]
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Returns the size in pixels.
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn create_textures(
+        cx: &Context,
+        format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        samples: u32,
+        width: u32,
+        height: u32,
+    ) -> (Texture, Option<Texture>, Option<Texture>) {
+        let color = RenderTexture {
+            label: Some("e2 render target color"),
+            format,
+            samples: 1,
+            width,
+            height,
+            binding: true,
+        }
+        .create(cx);
+
+        let msaa = (samples > 1).then(|| {
+            RenderTexture {
+                label: Some("e2 render target msaa"),
+                format,
+                samples,
+                width,
+                height,
+                binding: false,
+            }
+            .create(cx)
+        });
+
+        let depth = depth_format.map(|format| {
+            RenderTexture {
+                label: Some("e2 render target depth"),
+                format,
+                samples,
+                width,
+                height,
+                binding: false,
+            }
+            .create(cx)
+        });
+
+        (color, msaa, depth)
+    }
+}
+
+pub(crate) fn has_stencil(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Depth24PlusStencil8
+            | wgpu::TextureFormat::Depth24UnormStencil8
+            | wgpu::TextureFormat::Depth32FloatStencil8
+    )
+}
+
+impl<'a> From<&'a RenderTarget> for SpriteContent<'a> {
+    fn from(target: &'a RenderTarget) -> Self {
+        SpriteContent::Textured {
+            texture: &target.color,
+            src_rect: Rect::ONE,
+        }
+    }
+}
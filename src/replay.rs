@@ -0,0 +1,597 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use wgpu_glyph::ab_glyph;
+
+/// Version of the replay format written by this version of e2.
+///
+/// Replays with a different version are rejected by [Replay::parse].
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Text encoding of a [Replay].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayFormat {
+    Ron,
+    Json,
+}
+
+impl ReplayFormat {
+    /// Picks the format from the extension of `path`; anything other than `json` is RON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ReplayFormat::Json,
+            _ => ReplayFormat::Ron,
+        }
+    }
+}
+
+/// Where a texture or font referenced by a [Replay] comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResourceSource {
+    /// Loaded from a file, relative to [ReplayResources::root].
+    Path(PathBuf),
+    /// Looked up by hash in [ReplayResources]; see [resource_hash].
+    Hash(String),
+}
+
+/// A texture referenced by a [Replay].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTexture {
+    pub source: ResourceSource,
+    /// Whether the image is loaded as sRGB; only used for [ResourceSource::Path].
+    pub srgb: bool,
+}
+
+/// A mesh recorded by value in a [Replay].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayMesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+/// Owned version of [TextDraw].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayText {
+    pub origin: glam::Vec2,
+    pub depth: f32,
+    pub bounds: Option<glam::Vec2>,
+    pub text: String,
+    pub scale: f32,
+    pub color: Color,
+}
+
+impl ReplayText {
+    /// Returns the [TextDraw] borrowing from this text.
+    pub fn as_draw(&self) -> TextDraw<'_> {
+        TextDraw {
+            origin: self.origin,
+            depth: self.depth,
+            bounds: self.bounds,
+            text: &self.text,
+            scale: self.scale,
+            color: self.color,
+        }
+    }
+}
+
+impl From<&TextDraw<'_>> for ReplayText {
+    fn from(draw: &TextDraw) -> Self {
+        ReplayText {
+            origin: draw.origin,
+            depth: draw.depth,
+            bounds: draw.bounds,
+            text: draw.text.to_owned(),
+            scale: draw.scale,
+            color: draw.color,
+        }
+    }
+}
+
+/// A single recorded draw in a [ReplayPass].
+///
+/// Textures, meshes and fonts are indices into the lists of the owning [Replay].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayCommand {
+    /// See [SpriteRenderer::draw]; a `texture` of `None` is a colored sprite.
+    Sprite {
+        texture: Option<usize>,
+        src_rect: Rect,
+        color: Color,
+        rect: Rect,
+        rotation: f32,
+    },
+    /// See [BatchRenderer::draw].
+    Batch {
+        mesh: usize,
+        texture: usize,
+        draws: Vec<BatchDraw>,
+    },
+    /// See [TextRenderer::draw].
+    Text { font: usize, draws: Vec<ReplayText> },
+}
+
+/// A recorded render pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayPass {
+    /// Color to clear to at the start of the pass, if any.
+    pub clear: Option<Color>,
+    /// Matrix that is premultiplied against all the transformations in the pass.
+    pub matrix: glam::Mat4,
+    pub commands: Vec<ReplayCommand>,
+}
+
+impl ReplayPass {
+    /// Records a sprite; see [SpriteRenderer::draw].
+    pub fn sprite(
+        &mut self,
+        texture: Option<usize>,
+        src_rect: Rect,
+        color: Color,
+        rect: Rect,
+        rotation: f32,
+    ) -> &mut Self {
+        self.commands.push(ReplayCommand::Sprite {
+            texture,
+            src_rect,
+            color,
+            rect,
+            rotation,
+        });
+        self
+    }
+
+    /// Records a batched mesh draw; see [BatchRenderer::draw].
+    pub fn batch(&mut self, mesh: usize, texture: usize, draws: &[BatchDraw]) -> &mut Self {
+        self.commands.push(ReplayCommand::Batch {
+            mesh,
+            texture,
+            draws: draws.to_vec(),
+        });
+        self
+    }
+
+    /// Records text; see [TextRenderer::draw].
+    pub fn text(&mut self, font: usize, draws: &[TextDraw]) -> &mut Self {
+        self.commands.push(ReplayCommand::Text {
+            font,
+            draws: draws.iter().map(ReplayText::from).collect(),
+        });
+        self
+    }
+}
+
+/// Serializable, versioned recording of the draws of a frame.
+///
+/// A replay is built by registering its resources and recording passes,
+/// saved to RON or JSON, and later rendered offscreen with [Replay::render].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    /// Size in pixels of the render target.
+    pub width: u32,
+    pub height: u32,
+    pub textures: Vec<ReplayTexture>,
+    pub meshes: Vec<ReplayMesh>,
+    pub fonts: Vec<ResourceSource>,
+    pub passes: Vec<ReplayPass>,
+}
+
+/// Resources used to resolve the [ResourceSource]s of a [Replay].
+#[derive(Debug, Clone, Default)]
+pub struct ReplayResources {
+    /// Directory that [ResourceSource::Path]s are relative to.
+    pub root: PathBuf,
+    /// Textures by hash.
+    pub textures: HashMap<String, Texture>,
+    /// Fonts by hash.
+    pub fonts: HashMap<String, ab_glyph::FontArc>,
+}
+
+impl Replay {
+    /// Creates a new, empty [Replay] rendering at `width` by `height` pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            width,
+            height,
+            textures: Vec::new(),
+            meshes: Vec::new(),
+            fonts: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Registers a texture, returning its index.
+    pub fn texture(&mut self, source: ResourceSource, srgb: bool) -> usize {
+        self.textures.push(ReplayTexture { source, srgb });
+        self.textures.len() - 1
+    }
+
+    /// Registers a mesh, returning its index.
+    pub fn mesh(&mut self, vertices: &[Vertex], indices: &[u32]) -> usize {
+        self.meshes.push(ReplayMesh {
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+        });
+        self.meshes.len() - 1
+    }
+
+    /// Registers a font, returning its index.
+    pub fn font(&mut self, source: ResourceSource) -> usize {
+        self.fonts.push(source);
+        self.fonts.len() - 1
+    }
+
+    /// Begins recording a new pass.
+    pub fn pass(&mut self, clear: Option<Color>, matrix: glam::Mat4) -> &mut ReplayPass {
+        self.passes.push(ReplayPass {
+            clear,
+            matrix,
+            commands: Vec::new(),
+        });
+        self.passes.last_mut().unwrap()
+    }
+
+    /// Serializes the replay into `format`.
+    pub fn to_string(&self, format: ReplayFormat) -> Result<String> {
+        match format {
+            ReplayFormat::Ron => ron::ser::to_string_pretty(self, Default::default())
+                .map_err(|e| Error::InvalidReplay(e.to_string())),
+            ReplayFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| Error::InvalidReplay(e.to_string()))
+            }
+        }
+    }
+
+    /// Deserializes a replay from `format`, checking its version.
+    pub fn parse(source: &str, format: ReplayFormat) -> Result<Self> {
+        let replay: Replay = match format {
+            ReplayFormat::Ron => {
+                ron::from_str(source).map_err(|e| Error::InvalidReplay(e.to_string()))?
+            }
+            ReplayFormat::Json => {
+                serde_json::from_str(source).map_err(|e| Error::InvalidReplay(e.to_string()))?
+            }
+        };
+        if replay.version != REPLAY_VERSION {
+            return Err(Error::UnsupportedReplayVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    /// Saves the replay to `path`, in the format given by its extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string(ReplayFormat::from_path(path))?)?;
+        Ok(())
+    }
+
+    /// Loads a replay from `path`, in the format given by its extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::parse(
+            &std::fs::read_to_string(path)?,
+            ReplayFormat::from_path(path),
+        )
+    }
+
+    /// Renders the replay into a new [RenderTarget], through the existing renderers.
+    ///
+    /// Sprites and batches are drawn with a [BatchRenderer] and text with a [TextRenderer],
+    /// in the order they were recorded. The frame is submitted before returning,
+    /// and before each run of sprites and batches that follows a text draw.
+    pub fn render(&self, cx: &Context, resources: &ReplayResources) -> Result<RenderTarget> {
+        const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+        const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+        let depth_stencil = wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        };
+
+        let textures = self
+            .textures
+            .iter()
+            .map(|texture| match &texture.source {
+                ResourceSource::Path(path) => Ok(ImageTexture::from_path(
+                    resources.root.join(path),
+                    texture.srgb,
+                )?
+                .create(cx)),
+                ResourceSource::Hash(hash) => {
+                    resources.textures.get(hash).cloned().ok_or_else(|| {
+                        Error::InvalidReplay(format!("unknown texture hash {}", hash))
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let meshes = self
+            .meshes
+            .iter()
+            .map(|mesh| Arc::new(Mesh::new(cx, &mesh.vertices, &mesh.indices)))
+            .collect::<Vec<_>>();
+
+        let mut fonts = self
+            .fonts
+            .iter()
+            .map(|font| {
+                let font = match font {
+                    ResourceSource::Path(path) => {
+                        ab_glyph::FontArc::try_from_vec(std::fs::read(resources.root.join(path))?)?
+                    }
+                    ResourceSource::Hash(hash) => {
+                        resources.fonts.get(hash).cloned().ok_or_else(|| {
+                            Error::InvalidReplay(format!("unknown font hash {}", hash))
+                        })?
+                    }
+                };
+                Ok(FontBrush::new(font, cx, FORMAT, depth_stencil.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let target = RenderTarget::new(cx, FORMAT, Some(DEPTH_FORMAT), 1, self.width, self.height);
+        let pipeline = BatchRenderPipeline::new(
+            cx,
+            1,
+            FORMAT,
+            Some(wgpu::BlendState::ALPHA_BLENDING),
+            Some(depth_stencil),
+        );
+        let sampler = SimpleSampler::linear_clamp().create(cx);
+        let mut renderer = BatchRenderer::new(&pipeline);
+        let mut text_renderer = TextRenderer::new();
+        let mut frame = Frame::new(cx);
+
+        let texture = |index: usize| {
+            textures
+                .get(index)
+                .ok_or_else(|| Error::InvalidReplay(format!("texture {} out of range", index)))
+        };
+
+        // reused for every run of sprites and batches, so their instance buffers are too
+        let mut list = DisplayList::new(cx);
+        let mut list_drawn = false;
+
+        for pass in &self.passes {
+            let mut clear = pass.clear;
+            let mut commands = pass.commands.iter().peekable();
+            list.set_matrix(pass.matrix);
+
+            loop {
+                // sprites and batches up until the next text draw share a render pass
+                list.clear();
+                while let Some(command) =
+                    commands.next_if(|command| !matches!(command, ReplayCommand::Text { .. }))
+                {
+                    match command {
+                        ReplayCommand::Sprite {
+                            texture: index,
+                            src_rect,
+                            color,
+                            rect,
+                            rotation,
+                        } => {
+                            list.push(DisplayCommand::Sprite {
+                                texture: index.map(texture).transpose()?.cloned(),
+                                src_rect: *src_rect,
+                                color: *color,
+                                rect: *rect,
+                                rotation: *rotation,
+                            });
+                        }
+                        ReplayCommand::Batch {
+                            mesh,
+                            texture: index,
+                            draws,
+                        } => {
+                            let mesh = meshes.get(*mesh).ok_or_else(|| {
+                                Error::InvalidReplay(format!("mesh {} out of range", mesh))
+                            })?;
+                            list.batch(mesh.clone(), texture(*index)?, draws);
+                        }
+                        ReplayCommand::Text { .. } => unreachable!(),
+                    }
+                }
+
+                if !list.is_empty() || clear.is_some() {
+                    if list_drawn && !list.is_empty() {
+                        // the list rewrites its buffers in place, so the runs drawn so far must be submitted first
+                        text_renderer.submit();
+                        std::mem::replace(&mut frame, Frame::new(cx)).submit(cx);
+                        text_renderer.free();
+                    }
+                    list_drawn |= !list.is_empty();
+
+                    let mut render_pass = target.begin_pass(&mut frame, clear.take());
+                    pipeline.bind(&mut render_pass, &mut renderer);
+                    renderer.bind_sampler(cx, &mut render_pass, &sampler);
+                    list.draw(cx, &mut render_pass, &mut renderer);
+                }
+
+                match commands.next() {
+                    Some(ReplayCommand::Text { font, draws }) => {
+                        let font = fonts.get_mut(*font).ok_or_else(|| {
+                            Error::InvalidReplay(format!("font {} out of range", font))
+                        })?;
+                        let draws = draws.iter().map(ReplayText::as_draw).collect::<Vec<_>>();
+                        text_renderer.draw(
+                            cx,
+                            font,
+                            &draws,
+                            &mut frame,
+                            &target.color.view,
+                            wgpu::RenderPassDepthStencilAttachment {
+                                view: &target.depth.as_ref().unwrap().view,
+                                depth_ops: Some(wgpu::Operations {
+                                    load: wgpu::LoadOp::Load,
+                                    store: true,
+                                }),
+                                stencil_ops: None,
+                            },
+                            pass.matrix,
+                            None,
+                        )?;
+                    }
+                    Some(_) => unreachable!(),
+                    None => break,
+                }
+            }
+        }
+
+        text_renderer.submit();
+        frame.submit(cx);
+        text_renderer.free();
+
+        Ok(target)
+    }
+}
+
+/// Computes the hash used to reference a resource by [ResourceSource::Hash].
+///
+/// This is a 64-bit FNV-1a hash of `data` in hexadecimal, which is stable across platforms and versions.
+pub fn resource_hash(data: &[u8]) -> String {
+    let hash = data.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(320, 240);
+        let texture = replay.texture(ResourceSource::Path("sprite.png".into()), true);
+        let hashed = replay.texture(ResourceSource::Hash(resource_hash(b"pixels")), false);
+        let mesh = replay.mesh(
+            &[
+                Vertex {
+                    pos: [0., 0.],
+                    uv: [0., 0.],
+                },
+                Vertex {
+                    pos: [1., 0.],
+                    uv: [1., 0.],
+                },
+                Vertex {
+                    pos: [0., 1.],
+                    uv: [0., 1.],
+                },
+            ],
+            &[0, 1, 2],
+        );
+        let font = replay.font(ResourceSource::Path("font.ttf".into()));
+
+        replay
+            .pass(Some(Color::BLACK), glam::Mat4::IDENTITY)
+            .sprite(
+                Some(texture),
+                Rect::ONE,
+                Color::WHITE,
+                Rect::new(10., 20., 32., 32.),
+                0.1,
+            )
+            .sprite(None, Rect::ONE, Color::RED, Rect::new(0., 0., 4., 4.), 0.);
+        replay
+            .pass(
+                None,
+                glam::Mat4::orthographic_rh(0., 320., 240., 0., 0., 1.),
+            )
+            .batch(
+                mesh,
+                hashed,
+                &[BatchDraw {
+                    color: Color::new(0.5, 0.25, 1., 0.75),
+                    src_rect: Rect::new(0., 0., 0.5, 0.5),
+                    transform: glam::Mat4::from_rotation_z(0.3),
+                }],
+            )
+            .text(
+                font,
+                &[TextDraw {
+                    origin: glam::vec2(8., 8.),
+                    depth: 0.5,
+                    bounds: Some(glam::vec2(100., 50.)),
+                    text: "Score: \"1\"\n",
+                    scale: 16.,
+                    color: Color::GREEN,
+                }],
+            );
+        replay
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        for format in [ReplayFormat::Ron, ReplayFormat::Json] {
+            let source = replay.to_string(format).unwrap();
+            assert_eq!(
+                Replay::parse(&source, format).unwrap(),
+                replay,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut replay = replay();
+        replay.version = REPLAY_VERSION + 1;
+        for format in [ReplayFormat::Ron, ReplayFormat::Json] {
+            let source = replay.to_string(format).unwrap();
+            assert!(matches!(
+                Replay::parse(&source, format),
+                Err(Error::UnsupportedReplayVersion(version)) if version == REPLAY_VERSION + 1
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_source() {
+        let source = replay().to_string(ReplayFormat::Json).unwrap();
+        assert!(matches!(
+            Replay::parse(&source, ReplayFormat::Ron),
+            Err(Error::InvalidReplay(_))
+        ));
+        assert!(matches!(
+            Replay::parse(&source[..source.len() / 2], ReplayFormat::Json),
+            Err(Error::InvalidReplay(_))
+        ));
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("frame.json")),
+            ReplayFormat::Json
+        );
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("frame.JSON")),
+            ReplayFormat::Json
+        );
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("frame.ron")),
+            ReplayFormat::Ron
+        );
+        assert_eq!(
+            ReplayFormat::from_path(Path::new("frame")),
+            ReplayFormat::Ron
+        );
+    }
+
+    #[test]
+    fn stable_resource_hash() {
+        assert_eq!(resource_hash(b""), "cbf29ce484222325");
+        assert_eq!(resource_hash(b"a"), "af63dc4c8601ec8c");
+    }
+}
@@ -10,6 +10,7 @@ static NEXT_SAMPLER_ID: AtomicU64 = AtomicU64::new(0);
 #[derive(Debug, Clone)]
 pub struct Sampler {
     pub sampler: Arc<wgpu::Sampler>,
+    ty: Option<wgpu::SamplerBindingType>,
     id: u64,
 }
 
@@ -18,10 +19,25 @@ impl Sampler {
     pub fn new(sampler: Arc<wgpu::Sampler>) -> Self {
         Sampler {
             sampler,
+            ty: None,
             id: NEXT_SAMPLER_ID.fetch_add(1, SeqCst),
         }
     }
 
+    /// Creates a new [Sampler] from an existing sampler which binds as `ty`.
+    pub fn with_type(sampler: Arc<wgpu::Sampler>, ty: wgpu::SamplerBindingType) -> Self {
+        Sampler {
+            ty: Some(ty),
+            ..Sampler::new(sampler)
+        }
+    }
+
+    /// Returns the binding type of the sampler, if known.
+    #[inline]
+    pub fn ty(&self) -> Option<wgpu::SamplerBindingType> {
+        self.ty
+    }
+
     /// Returns an ID uniquely identifying this [Sampler].
     #[inline]
     pub fn id(&self) -> u64 {
@@ -31,7 +47,9 @@ impl Sampler {
 
 /// Simplified sampler descriptor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct SimpleSampler {
+pub struct SimpleSampler<'a> {
+    /// Debug label of the sampler.
+    pub label: Option<&'a str>,
     pub clamp_u: wgpu::AddressMode,
     pub clamp_v: wgpu::AddressMode,
     pub clamp_w: wgpu::AddressMode,
@@ -39,10 +57,11 @@ pub struct SimpleSampler {
     pub min: wgpu::FilterMode,
 }
 
-impl SimpleSampler {
+impl<'a> SimpleSampler<'a> {
     /// Sampler with linear filtering and clamped address modes in all directions.
     pub fn linear_clamp() -> Self {
         SimpleSampler {
+            label: None,
             clamp_u: wgpu::AddressMode::ClampToEdge,
             clamp_v: wgpu::AddressMode::ClampToEdge,
             clamp_w: wgpu::AddressMode::ClampToEdge,
@@ -56,6 +75,7 @@ impl SimpleSampler {
     /// Ideal for pixel art.
     pub fn nearest_clamp() -> Self {
         SimpleSampler {
+            label: None,
             clamp_u: wgpu::AddressMode::ClampToEdge,
             clamp_v: wgpu::AddressMode::ClampToEdge,
             clamp_w: wgpu::AddressMode::ClampToEdge,
@@ -65,21 +85,29 @@ impl SimpleSampler {
     }
 
     /// Creates a new [Sampler] from the stored sampler configuration.
+    ///
+    /// Mipmaps are filtered like `min`, so samplers with only nearest filtering
+    /// are non-filtering and can sample non-filterable textures.
     pub fn create(self, cx: &Context) -> Sampler {
         let sampler = cx.device.create_sampler(&wgpu::SamplerDescriptor {
-            label: None,
+            label: self.label,
             address_mode_u: self.clamp_u,
             address_mode_v: self.clamp_v,
             address_mode_w: self.clamp_w,
             mag_filter: self.mag,
             min_filter: self.min,
-            mipmap_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: self.min,
             lod_min_clamp: 0.,
             lod_max_clamp: 1.,
             compare: None,
             anisotropy_clamp: None,
             border_color: None,
         });
-        Sampler::new(Arc::new(sampler))
+        let ty = if self.mag == wgpu::FilterMode::Linear || self.min == wgpu::FilterMode::Linear {
+            wgpu::SamplerBindingType::Filtering
+        } else {
+            wgpu::SamplerBindingType::NonFiltering
+        };
+        Sampler::with_type(Arc::new(sampler), ty)
     }
 }
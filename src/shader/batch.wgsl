@@ -1,15 +1,12 @@
+#include "e2/draw"
+#include "e2/color"
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
     @location(1) @interpolate(flat) idx: u32,
 };
 
-struct Draw {                   //              align(16)    size(96)
-    color: vec4<f32>,           // offset(0)    align(16)    size(16)
-    src_rect: vec4<f32>,        // offset(16)   align(16)    size(16)
-    transform: mat4x4<f32>,     // offset(32)   align(16)    size(64)
-};
-
 struct Draws {
     draws: array<Draw>,
 };
@@ -32,8 +29,8 @@ fn vs_main(
     var instance = instances.draws[in_instance_index];
 
     var out: VertexOutput;
-    out.position = instance.transform * vec4<f32>(position, 0.0, 1.0);
-    out.uv = mix(instance.src_rect.xy, instance.src_rect.zw, uv);
+    out.position = draw_position(instance, position);
+    out.uv = draw_uv(instance, uv);
     out.idx = in_instance_index;
     return out;
 }
@@ -41,4 +38,10 @@ fn vs_main(
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return instances.draws[in.idx].color * textureSample(t, s, in.uv);
-}
\ No newline at end of file
+}
+
+// Premultiplied textures; the tint color is still straight alpha.
+@fragment
+fn fs_premultiplied(in: VertexOutput) -> @location(0) vec4<f32> {
+    return premultiply(instances.draws[in.idx].color) * textureSample(t, s, in.uv);
+}
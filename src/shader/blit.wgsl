@@ -0,0 +1,40 @@
+#include "e2/color"
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var t: texture_2d<f32>;
+
+@group(1) @binding(0)
+var s: sampler;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t, s, in.uv);
+}
+
+@fragment
+fn fs_srgb_to_linear(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t, s, in.uv);
+    return vec4<f32>(srgb_to_linear(color.rgb), color.a);
+}
+
+@fragment
+fn fs_linear_to_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(t, s, in.uv);
+    return vec4<f32>(linear_to_srgb(color.rgb), color.a);
+}
@@ -0,0 +1,15 @@
+struct Draw {                   //              align(16)    size(96)
+    color: vec4<f32>,           // offset(0)    align(16)    size(16)
+    src_rect: vec4<f32>,        // offset(16)   align(16)    size(16)
+    transform: mat4x4<f32>,     // offset(32)   align(16)    size(64)
+};
+
+// Clip space position of a mesh vertex.
+fn draw_position(draw: Draw, position: vec2<f32>) -> vec4<f32> {
+    return draw.transform * vec4<f32>(position, 0.0, 1.0);
+}
+
+// Texture coordinates of a mesh UV within the source rectangle.
+fn draw_uv(draw: Draw, uv: vec2<f32>) -> vec2<f32> {
+    return mix(draw.src_rect.xy, draw.src_rect.zw, uv);
+}
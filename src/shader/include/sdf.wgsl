@@ -0,0 +1,26 @@
+// Signed distance functions; negative inside the shape.
+
+fn sdf_circle(p: vec2<f32>, radius: f32) -> f32 {
+    return length(p) - radius;
+}
+
+fn sdf_box(p: vec2<f32>, half_size: vec2<f32>) -> f32 {
+    let d = abs(p) - half_size;
+    return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0);
+}
+
+fn sdf_rounded_box(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    return sdf_box(p, half_size - vec2<f32>(radius)) - radius;
+}
+
+fn sdf_segment(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / dot(ba, ba), 0.0, 1.0);
+    return length(pa - ba * h);
+}
+
+// Anti-aliased coverage of a distance; fragment stage only.
+fn sdf_coverage(d: f32) -> f32 {
+    return clamp(0.5 - d / max(fwidth(d), 0.0001), 0.0, 1.0);
+}
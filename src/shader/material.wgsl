@@ -0,0 +1,34 @@
+#include "e2/draw"
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // texture coordinates within the source rectangle
+    @location(0) uv: vec2<f32>,
+    // mesh coordinates, before the source rectangle is applied
+    @location(1) local_uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var t: texture_2d<f32>;
+
+@group(2) @binding(0)
+var s: sampler;
+
+fn draw_vertex(input: VertexInput, draw: Draw) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = draw_position(draw, input.position);
+    out.uv = draw_uv(draw, input.uv);
+    out.local_uv = input.uv;
+    out.color = draw.color;
+    return out;
+}
+
+fn sample_texture(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(t, s, uv);
+}
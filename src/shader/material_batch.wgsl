@@ -0,0 +1,16 @@
+struct Draws {
+    draws: array<Draw>,
+};
+
+@group(0) @binding(0)
+var<storage, read> instances: Draws;
+
+@vertex
+fn vs_main(@builtin(instance_index) instance: u32, input: VertexInput) -> VertexOutput {
+    return material_vertex(input, instances.draws[instance]);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return material_fragment(in);
+}
@@ -0,0 +1,12 @@
+@group(0) @binding(0)
+var<uniform> uniforms: Draw;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    return material_vertex(input, uniforms);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return material_fragment(in);
+}
@@ -1,14 +1,11 @@
+#include "e2/draw"
+#include "e2/color"
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) uv: vec2<f32>,
 };
 
-struct Draw {
-    color: vec4<f32>,
-    src_rect: vec4<f32>,
-    transform: mat4x4<f32>,
-};
-
 @group(0) @binding(0)
 var<uniform> uniforms: Draw;
 
@@ -24,12 +21,18 @@ fn vs_main(
     @location(1) uv: vec2<f32>,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = uniforms.transform * vec4<f32>(position, 0.0, 1.0);
-    out.uv = mix(uniforms.src_rect.xy, uniforms.src_rect.zw, uv);
+    out.position = draw_position(uniforms, position);
+    out.uv = draw_uv(uniforms, uv);
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return uniforms.color * textureSample(t, s, in.uv);
-}
\ No newline at end of file
+}
+
+// Premultiplied textures; the tint color is still straight alpha.
+@fragment
+fn fs_premultiplied(in: VertexOutput) -> @location(0) vec4<f32> {
+    return premultiply(uniforms.color) * textureSample(t, s, in.uv);
+}
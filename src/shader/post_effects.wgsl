@@ -0,0 +1,108 @@
+#include "e2/color"
+
+struct Params {
+    a: vec4<f32>,
+    b: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> params: Params;
+
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_source(in.uv);
+}
+
+// a.xy = direction, a.z = sigma in pixels
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4<f32> {
+    let sigma = max(params.a.z, 0.001);
+    let step = params.a.xy * texel_size();
+    let taps = min(i32(ceil(sigma * 3.0)), 32);
+
+    var sum = sample_source(in.uv);
+    var total = 1.0;
+    for (var i = 1; i <= taps; i = i + 1) {
+        let x = f32(i);
+        let weight = exp(-(x * x) / (2.0 * sigma * sigma));
+        sum = sum + weight * (sample_source(in.uv + step * x) + sample_source(in.uv - step * x));
+        total = total + 2.0 * weight;
+    }
+    return sum / total;
+}
+
+// a.x = luminance threshold
+@fragment
+fn fs_threshold(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+    let lum = luminance(color.rgb);
+    let scale = max(lum - params.a.x, 0.0) / max(lum, 0.0001);
+    return vec4<f32>(color.rgb * scale, color.a);
+}
+
+// a.x = intensity; extra = unblurred input
+@fragment
+fn fs_bloom(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base = sample_extra(in.uv);
+    let bloom = sample_source(in.uv);
+    return vec4<f32>(base.rgb + bloom.rgb * params.a.x, base.a);
+}
+
+// a.x = intensity, a.y = radius, a.z = smoothness; b = color
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+    let dist = distance(in.uv, vec2<f32>(0.5, 0.5)) * 1.41421356;
+    let factor = smoothstep(params.a.y, params.a.y + max(params.a.z, 0.0001), dist) * params.a.x;
+    return vec4<f32>(mix(color.rgb, params.b.rgb, factor * params.b.a), color.a);
+}
+
+// a.x = intensity; extra = LUT laid out as a horizontal strip of N slices of N by N
+@fragment
+fn fs_lut(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+    let size = f32(textureDimensions(extra).y);
+    let c = clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    let blue = c.b * (size - 1.0);
+    let slice0 = floor(blue);
+    let slice1 = min(slice0 + 1.0, size - 1.0);
+    let x = c.r * (size - 1.0) + 0.5;
+    let y = (c.g * (size - 1.0) + 0.5) / size;
+
+    let graded0 = sample_extra(vec2<f32>((slice0 * size + x) / (size * size), y)).rgb;
+    let graded1 = sample_extra(vec2<f32>((slice1 * size + x) / (size * size), y)).rgb;
+    let graded = mix(graded0, graded1, blue - slice0);
+
+    return vec4<f32>(mix(color.rgb, graded, params.a.x), color.a);
+}
+
+// a.x = offset in pixels at the edges
+@fragment
+fn fs_chromatic(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = (in.uv - vec2<f32>(0.5, 0.5)) * 2.0 * params.a.x * texel_size();
+    let r = sample_source(in.uv + offset).r;
+    let center = sample_source(in.uv);
+    let b = sample_source(in.uv - offset).b;
+    return vec4<f32>(r, center.g, b, center.a);
+}
+
+// a.x = pixel size in pixels
+@fragment
+fn fs_pixelate(in: VertexOutput) -> @location(0) vec4<f32> {
+    let cell = max(params.a.x, 1.0) * texel_size();
+    return sample_source((floor(in.uv / cell) + 0.5) * cell);
+}
+
+// a.x = curvature, a.y = scanline intensity, a.z = scanline count
+@fragment
+fn fs_crt(in: VertexOutput) -> @location(0) vec4<f32> {
+    var uv = in.uv * 2.0 - 1.0;
+    uv = uv * (1.0 + params.a.x * dot(uv.yx, uv.yx));
+    uv = uv * 0.5 + 0.5;
+
+    let color = sample_source(uv);
+    let scanline = 1.0 - params.a.y * (0.5 + 0.5 * sin(uv.y * params.a.z * 3.14159265));
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, color.a), vec4<f32>(color.rgb * scanline, color.a), inside);
+}
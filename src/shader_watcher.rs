@@ -0,0 +1,164 @@
+use crate::*;
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll, RawWaker, RawWakerVTable, Waker},
+    time::SystemTime,
+};
+
+type PipelineBuilder = Box<dyn Fn(&Context, &wgpu::ShaderModule) -> wgpu::RenderPipeline>;
+
+/// Handle to a pipeline watched by a [ShaderWatcher].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(usize);
+
+struct Watched {
+    path: PathBuf,
+    preprocessor: ShaderPreprocessor,
+    build: PipelineBuilder,
+    modified: Option<SystemTime>,
+    pipeline: Arc<wgpu::RenderPipeline>,
+}
+
+/// Rebuilds pipelines when their WGSL files change on disk, for use during development.
+///
+/// Files are processed by a [ShaderPreprocessor] and validated with naga before rebuilding,
+/// so that a broken shader keeps the previous pipeline instead of aborting.
+/// Validation errors raised by wgpu while creating the pipeline, such as a binding which does not match
+/// the pipeline layout, are caught in an error scope and reported the same way.
+/// On the web, where error scopes resolve asynchronously, those are still reported by wgpu instead.
+#[derive(Default)]
+pub struct ShaderWatcher {
+    watched: Vec<Watched>,
+}
+
+impl ShaderWatcher {
+    /// Creates a new [ShaderWatcher] which watches nothing.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Builds a pipeline from the WGSL file at `path` and watches the file.
+    ///
+    /// `build` creates the pipeline from the compiled shader, typically with [SimpleRenderPipeline],
+    /// and is called again whenever the file changes.
+    /// Fails if the initial shader cannot be read or is invalid.
+    pub fn watch(
+        &mut self,
+        cx: &Context,
+        path: impl Into<PathBuf>,
+        preprocessor: ShaderPreprocessor,
+        build: impl Fn(&Context, &wgpu::ShaderModule) -> wgpu::RenderPipeline + 'static,
+    ) -> Result<WatchId> {
+        let path = path.into();
+        let modified = std::fs::metadata(&path)?.modified().ok();
+        let build: PipelineBuilder = Box::new(build);
+        let pipeline = Self::build(cx, &path, &preprocessor, &build)?;
+
+        self.watched.push(Watched {
+            path,
+            preprocessor,
+            build,
+            modified,
+            pipeline: Arc::new(pipeline),
+        });
+        Ok(WatchId(self.watched.len() - 1))
+    }
+
+    /// Returns the latest successfully built pipeline of `id`.
+    #[inline]
+    pub fn pipeline(&self, id: WatchId) -> Arc<wgpu::RenderPipeline> {
+        self.watched[id.0].pipeline.clone()
+    }
+
+    /// Returns the path of the file watched for `id`.
+    #[inline]
+    pub fn path(&self, id: WatchId) -> &Path {
+        &self.watched[id.0].path
+    }
+
+    /// Rebuilds the pipelines whose files were modified since the last poll.
+    ///
+    /// Returns the outcome of every rebuild; on error the previous pipeline is kept.
+    /// A file which cannot be accessed is reported once, until it reappears.
+    ///
+    /// This checks the modification time of every watched file, so call it at most once per frame.
+    pub fn poll(&mut self, cx: &Context) -> Vec<(WatchId, Result<()>)> {
+        let mut results = vec![];
+        for (index, watched) in self.watched.iter_mut().enumerate() {
+            let modified = match std::fs::metadata(&watched.path).and_then(|meta| meta.modified()) {
+                Ok(modified) => modified,
+                Err(err) => {
+                    if watched.modified.take().is_some() {
+                        results.push((WatchId(index), Err(err.into())));
+                    }
+                    continue;
+                }
+            };
+
+            if watched.modified != Some(modified) {
+                watched.modified = Some(modified);
+                results.push((WatchId(index), watched.rebuild(cx)));
+            }
+        }
+        results
+    }
+
+    /// Rebuilds the pipeline of `id` regardless of whether its file changed.
+    ///
+    /// On error the previous pipeline is kept.
+    pub fn reload(&mut self, cx: &Context, id: WatchId) -> Result<()> {
+        self.watched[id.0].rebuild(cx)
+    }
+
+    fn build(
+        cx: &Context,
+        path: &Path,
+        preprocessor: &ShaderPreprocessor,
+        build: &PipelineBuilder,
+    ) -> Result<wgpu::RenderPipeline> {
+        let source = std::fs::read_to_string(path)?;
+        let file = path.to_string_lossy();
+        let shader = preprocessor.process(&file, &source)?;
+        shader.validate()?;
+
+        cx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = build(cx, &shader.create(cx, Some(&file)));
+        match now_or_never(cx.device.pop_error_scope()).flatten() {
+            Some(err) => Err(Error::Shader {
+                file: file.into_owned(),
+                line: 0,
+                column: 0,
+                message: err.to_string().trim_end().to_owned(),
+            }),
+            None => Ok(pipeline),
+        }
+    }
+}
+
+/// Polls `future` once, returning its output if it is already complete.
+///
+/// Error scopes resolve immediately on native backends.
+fn now_or_never<F: Future>(future: F) -> Option<F::Output> {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(std::ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+    let waker = unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) };
+    match pin!(future).poll(&mut TaskContext::from_waker(&waker)) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+impl Watched {
+    fn rebuild(&mut self, cx: &Context) -> Result<()> {
+        let pipeline = ShaderWatcher::build(cx, &self.path, &self.preprocessor, &self.build)?;
+        self.pipeline = Arc::new(pipeline);
+        Ok(())
+    }
+}
@@ -18,7 +18,7 @@ impl SpriteBatchRenderer {
         let renderer = BatchRenderer::new(pipeline);
 
         let rect = Mesh::new(
-            &cx,
+            cx,
             &[
                 Vertex {
                     pos: [0., 0.],
@@ -41,12 +41,13 @@ impl SpriteBatchRenderer {
         );
 
         let white = ImageTexture {
+            label: Some("e2 white texture"),
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             pixels: Cow::Borrowed(&[255, 255, 255, 255]),
             width: 1,
             height: 1,
         }
-        .create(&cx);
+        .create(cx);
 
         SpriteBatchRenderer {
             renderer,
@@ -64,15 +65,24 @@ impl SpriteBatchRenderer {
     }
 
     /// Binds a sampler for use with the proceeding draw calls.
-    pub fn bind_sampler<'a>(
-        &mut self,
-        cx: &Context,
-        pass: &'a mut wgpu::RenderPass,
-        sampler: &Sampler,
-    ) {
+    pub fn bind_sampler(&mut self, cx: &Context, pass: &mut wgpu::RenderPass, sampler: &Sampler) {
         self.renderer.bind_sampler(cx, pass, sampler);
     }
 
+    /// Switches the blend mode for the proceeding draw calls.
+    ///
+    /// See [BatchRenderer::set_blend].
+    pub fn set_blend(&mut self, cx: &Context, pass: &mut ArenaRenderPass, blend: BlendMode) {
+        self.renderer.set_blend(cx, pass, blend);
+    }
+
+    /// Binds the built-in pipeline matching the format of `pass`.
+    ///
+    /// See [BatchRenderer::bind_pass].
+    pub fn bind_pass(&mut self, cx: &Context, pass: &mut ArenaRenderPass) {
+        self.renderer.bind_pass(cx, pass);
+    }
+
     /// Sets the matrix that is premultiplied against the sprite transformation matrices.
     pub fn set_matrix(&mut self, matrix: glam::Mat4) {
         self.matrix = matrix;
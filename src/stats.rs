@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Snapshot of rendering statistics for the current frame.
+///
+/// See [Context::stats](crate::Context::stats) and [Context::take_stats](crate::Context::take_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FrameStats {
+    /// Number of draw calls issued.
+    pub draw_calls: u64,
+    /// Number of instances drawn, summed over all draw calls.
+    pub instances: u64,
+    /// Number of text sections queued for drawing.
+    pub text_sections: u64,
+    /// Number of bind groups created.
+    pub bind_groups_created: u64,
+    /// Number of GPU buffers allocated.
+    pub buffers_allocated: u64,
+    /// Number of bytes written to GPU buffers.
+    pub bytes_uploaded: u64,
+}
+
+/// Counters that the built-in renderers report into.
+///
+/// These are reset by [Context::take_stats](crate::Context::take_stats).
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    draw_calls: AtomicU64,
+    instances: AtomicU64,
+    text_sections: AtomicU64,
+    bind_groups_created: AtomicU64,
+    buffers_allocated: AtomicU64,
+    bytes_uploaded: AtomicU64,
+}
+
+impl StatsCounters {
+    /// Records a draw call of `instances` instances.
+    #[inline]
+    pub fn draw(&self, instances: u64) {
+        self.draw_calls.fetch_add(1, Relaxed);
+        self.instances.fetch_add(instances, Relaxed);
+    }
+
+    /// Records `sections` text sections being queued.
+    #[inline]
+    pub fn text(&self, sections: u64) {
+        self.text_sections.fetch_add(sections, Relaxed);
+    }
+
+    /// Records a bind group creation.
+    #[inline]
+    pub fn bind_group_created(&self) {
+        self.bind_groups_created.fetch_add(1, Relaxed);
+    }
+
+    /// Records a buffer allocation.
+    #[inline]
+    pub fn buffer_allocated(&self) {
+        self.buffers_allocated.fetch_add(1, Relaxed);
+    }
+
+    /// Records `bytes` bytes being written to a GPU buffer.
+    #[inline]
+    pub fn uploaded(&self, bytes: u64) {
+        self.bytes_uploaded.fetch_add(bytes, Relaxed);
+    }
+
+    /// Returns the current value of the counters and resets them to zero.
+    pub fn take(&self) -> FrameStats {
+        FrameStats {
+            draw_calls: self.draw_calls.swap(0, Relaxed),
+            instances: self.instances.swap(0, Relaxed),
+            text_sections: self.text_sections.swap(0, Relaxed),
+            bind_groups_created: self.bind_groups_created.swap(0, Relaxed),
+            buffers_allocated: self.buffers_allocated.swap(0, Relaxed),
+            bytes_uploaded: self.bytes_uploaded.swap(0, Relaxed),
+        }
+    }
+
+    /// Returns the current value of the counters.
+    pub fn snapshot(&self) -> FrameStats {
+        FrameStats {
+            draw_calls: self.draw_calls.load(Relaxed),
+            instances: self.instances.load(Relaxed),
+            text_sections: self.text_sections.load(Relaxed),
+            bind_groups_created: self.bind_groups_created.load(Relaxed),
+            buffers_allocated: self.buffers_allocated.load(Relaxed),
+            bytes_uploaded: self.bytes_uploaded.load(Relaxed),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::*;
+
+/// A window surface along with its current configuration.
+///
+/// Any number of surfaces can share a single [Context], and thus share pipelines and textures.
+#[derive(Debug)]
+pub struct WindowSurface {
+    pub surface: wgpu::Surface,
+    config: Option<wgpu::SurfaceConfiguration>,
+}
+
+impl WindowSurface {
+    /// Creates a new [WindowSurface] rendering to `window`.
+    ///
+    /// The adapter of `cx` must be compatible with `window`.
+    pub fn new(cx: &Context, window: &impl raw_window_handle::HasRawWindowHandle) -> Self {
+        Self::from_surface(unsafe { cx.instance.create_surface(window) })
+    }
+
+    /// Creates a new [WindowSurface] from an existing, unconfigured surface.
+    pub fn from_surface(surface: wgpu::Surface) -> Self {
+        WindowSurface {
+            surface,
+            config: None,
+        }
+    }
+
+    /// Configures the surface with `width` and `height` in pixels and with `present_mode` presentation.
+    ///
+    /// The first supported format matching `srgb` is chosen, falling back to the preferred format of the surface.
+    /// Query the chosen format with [WindowSurface::format].
+    pub fn configure(
+        &mut self,
+        cx: &Context,
+        width: u32,
+        height: u32,
+        present_mode: wgpu::PresentMode,
+        srgb: bool,
+    ) {
+        let formats = self.surface.get_supported_formats(&cx.adapter);
+        let format = formats
+            .iter()
+            .copied()
+            .find(|format| format.describe().srgb == srgb)
+            .unwrap_or(formats[0]);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode,
+        };
+        self.surface.configure(&cx.device, &config);
+        self.config = Some(config);
+    }
+
+    /// Reconfigures the surface with a new size in pixels, keeping the rest of the configuration.
+    ///
+    /// Does nothing if the surface has not been configured, if the size is unchanged, or if either dimension is zero.
+    /// Returns `true` if the surface was reconfigured.
+    ///
+    /// Size-dependent textures such as [RenderTarget] should be resized to [WindowSurface::size] afterwards.
+    pub fn resize(&mut self, cx: &Context, width: u32, height: u32) -> bool {
+        match &mut self.config {
+            Some(config)
+                if width > 0 && height > 0 && (config.width, config.height) != (width, height) =>
+            {
+                config.width = width;
+                config.height = height;
+                self.surface.configure(&cx.device, config);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the current configuration, if the surface has been configured.
+    #[inline]
+    pub fn config(&self) -> Option<&wgpu::SurfaceConfiguration> {
+        self.config.as_ref()
+    }
+
+    /// Returns the current format, if the surface has been configured.
+    ///
+    /// Pipelines rendering to the surface should be created with this format.
+    #[inline]
+    pub fn format(&self) -> Option<wgpu::TextureFormat> {
+        self.config.as_ref().map(|config| config.format)
+    }
+
+    /// Returns the current size in pixels, if the surface has been configured.
+    #[inline]
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.config
+            .as_ref()
+            .map(|config| (config.width, config.height))
+    }
+
+    /// Helper function for [wgpu::Surface::get_current_texture]
+    ///
+    /// If the surface is outdated or lost, it is reconfigured and acquisition is retried once.
+    pub fn next_frame(&self, cx: &Context) -> Result<wgpu::SurfaceTexture> {
+        match self.surface.get_current_texture() {
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                if let Some(config) = &self.config {
+                    self.surface.configure(&cx.device, config);
+                }
+                Ok(self.surface.get_current_texture()?)
+            }
+            result => Ok(result?),
+        }
+    }
+}
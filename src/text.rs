@@ -1,9 +1,11 @@
 use crate::*;
-use std::path::Path;
-use wgpu_glyph::ab_glyph;
+use std::{ops::Range, path::Path};
+use wgpu_glyph::ab_glyph::{self, Font as _};
 
 /// Font data that is readily available to be rendered.
 ///
+/// A brush can hold an ordered chain of fallback fonts; see [FontBrush::with_fallbacks].
+///
 /// This **requires** a depth stencil state as text draw calls cannot be visually interleaved with regular draw calls otherwise.
 #[derive(Debug)]
 pub struct FontBrush {
@@ -67,11 +69,102 @@ impl FontBrush {
         format: wgpu::TextureFormat,
         depth_stencil: wgpu::DepthStencilState,
     ) -> Self {
-        let brush = wgpu_glyph::GlyphBrushBuilder::using_font(handle)
+        Self::with_fallbacks(vec![handle], cx, format, depth_stencil)
+    }
+
+    /// Creates a new [FontBrush] from an ordered list of fonts.
+    ///
+    /// Each character is rendered with the first font in `fonts` that has a glyph for it.
+    /// Characters that no font covers, along with whitespace and control characters,
+    /// are rendered with the font of the preceding characters (or the first font at the start of the text).
+    ///
+    /// Panics if `fonts` is empty.
+    pub fn with_fallbacks(
+        fonts: Vec<ab_glyph::FontArc>,
+        cx: &Context,
+        format: wgpu::TextureFormat,
+        depth_stencil: wgpu::DepthStencilState,
+    ) -> Self {
+        assert!(!fonts.is_empty(), "FontBrush requires at least one font");
+        let brush = wgpu_glyph::GlyphBrushBuilder::using_fonts(fonts)
             .depth_stencil_state(depth_stencil)
             .build(&cx.device, format);
         FontBrush { brush }
     }
+
+    /// Appends `font` to the end of the fallback chain.
+    pub fn add_fallback(&mut self, font: ab_glyph::FontArc) -> wgpu_glyph::FontId {
+        self.brush.add_font(font)
+    }
+
+    /// Returns the first font in the fallback chain that has a glyph for `c`.
+    #[inline]
+    pub fn font_for(&self, c: char) -> Option<wgpu_glyph::FontId> {
+        font_for(self.brush.fonts(), c)
+    }
+
+    /// Returns `true` if any font in the fallback chain has a glyph for `c`.
+    ///
+    /// Whitespace and control characters are always considered covered.
+    #[inline]
+    pub fn covers(&self, c: char) -> bool {
+        covers(self.brush.fonts(), c)
+    }
+
+    /// Returns the unique characters in `text` that no font in the fallback chain covers, in order of appearance.
+    ///
+    /// Useful for warning about text that will render as missing glyphs.
+    #[inline]
+    pub fn missing_chars(&self, text: &str) -> Vec<char> {
+        missing_chars(self.brush.fonts(), text)
+    }
+
+    #[inline]
+    fn runs(&self, text: &str) -> Vec<(Range<usize>, wgpu_glyph::FontId)> {
+        runs(self.brush.fonts(), text)
+    }
+}
+
+fn font_for(fonts: &[ab_glyph::FontArc], c: char) -> Option<wgpu_glyph::FontId> {
+    fonts
+        .iter()
+        .position(|font| font.glyph_id(c).0 != 0)
+        .map(wgpu_glyph::FontId)
+}
+
+fn covers(fonts: &[ab_glyph::FontArc], c: char) -> bool {
+    c.is_whitespace() || c.is_control() || font_for(fonts, c).is_some()
+}
+
+fn missing_chars(fonts: &[ab_glyph::FontArc], text: &str) -> Vec<char> {
+    let mut missing = Vec::new();
+    for c in text.chars() {
+        if !covers(fonts, c) && !missing.contains(&c) {
+            missing.push(c);
+        }
+    }
+    missing
+}
+
+/// Splits `text` into runs of characters that share the same font.
+///
+/// Whitespace, control and uncovered characters stay in the current run.
+fn runs(fonts: &[ab_glyph::FontArc], text: &str) -> Vec<(Range<usize>, wgpu_glyph::FontId)> {
+    let mut runs: Vec<(Range<usize>, wgpu_glyph::FontId)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        let font = if c.is_whitespace() || c.is_control() {
+            None
+        } else {
+            font_for(fonts, c)
+        };
+        match (runs.last_mut(), font) {
+            (Some((range, current)), Some(font)) if *current == font => range.end = end,
+            (Some((range, _)), None) => range.end = end,
+            (_, font) => runs.push((i..end, font.unwrap_or_default())),
+        }
+    }
+    runs
 }
 
 /// Text rendering helper type which renders text using a [FontBrush].
@@ -105,6 +198,7 @@ impl TextRenderer {
     /// Draws text into a specified target, with a given transform and optional clipping rectangle.
     ///
     /// Call this as least times as possible, batching text into arrays of [TextDraw] as much as is practicable.
+    #[allow(clippy::too_many_arguments)]
     pub fn draw(
         &mut self,
         cx: &Context,
@@ -119,6 +213,7 @@ impl TextRenderer {
         for draw in draws {
             self.queue(font, draw);
         }
+        cx.stats.text(draws.len() as _);
 
         let transform = transform.to_cols_array();
         if let Some(clip) = clip {
@@ -137,7 +232,8 @@ impl TextRenderer {
                         height: clip.size.y as _,
                     },
                 )
-                .map_err(|s| Error::TextDraw(s))?;
+                .map_err(Error::TextDraw)?;
+            cx.stats.draw(1);
         } else {
             font.brush
                 .draw_queued_with_transform(
@@ -148,29 +244,45 @@ impl TextRenderer {
                     depth_stencil,
                     transform,
                 )
-                .map_err(|s| Error::TextDraw(s))?;
+                .map_err(Error::TextDraw)?;
+            cx.stats.draw(1);
         }
 
         Ok(())
     }
 
     fn queue(&self, font: &mut FontBrush, draw: &TextDraw) {
+        let text = font
+            .runs(draw.text)
+            .into_iter()
+            .map(|(range, font_id)| {
+                wgpu_glyph::Text::default()
+                    .with_text(&draw.text[range])
+                    .with_font_id(font_id)
+                    .with_scale(draw.scale)
+                    .with_color([draw.color.r, draw.color.g, draw.color.b, draw.color.a])
+                    .with_z(draw.depth)
+            })
+            .collect();
+
         font.brush.queue(wgpu_glyph::Section {
             screen_position: (draw.origin.x, draw.origin.y),
             bounds: draw
                 .bounds
                 .map(|bounds| bounds.into())
                 .unwrap_or((f32::INFINITY, f32::INFINITY)),
-            text: vec![wgpu_glyph::Text::default()
-                .with_text(draw.text)
-                .with_scale(draw.scale)
-                .with_color([draw.color.r, draw.color.g, draw.color.b, draw.color.a])
-                .with_z(draw.depth)],
+            text,
             ..wgpu_glyph::Section::default()
         });
     }
 }
 
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Draw data for rendering text.
 pub struct TextDraw<'a> {
     /// Top-left of the text.
@@ -208,3 +320,100 @@ impl<'a> TextDraw<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal TrueType font with an (empty) glyph for each of `chars` only.
+    fn font(chars: &[char]) -> ab_glyph::FontArc {
+        let be16 = |value: u16| value.to_be_bytes().to_vec();
+        let be32 = |value: u32| value.to_be_bytes().to_vec();
+
+        let mut head = [be32(0x0001_0000), vec![0; 14], be16(1000), vec![0; 30]].concat();
+        head.extend(be16(0)); // index to location format
+        head.extend(be16(0)); // glyph data format
+        let hhea = [be32(0x0001_0000), vec![0; 30], be16(1)].concat();
+        let maxp = [be32(0x0000_5000), be16(chars.len() as u16 + 1)].concat();
+
+        // format 12 subtable mapping each character to its own glyph, after .notdef
+        let mut groups = Vec::new();
+        for (glyph, &c) in chars.iter().enumerate() {
+            groups.extend([be32(c as u32), be32(c as u32), be32(glyph as u32 + 1)].concat());
+        }
+        let subtable = [
+            be16(12),
+            be16(0),
+            be32(16 + groups.len() as u32),
+            be32(0),
+            be32(chars.len() as u32),
+            groups,
+        ]
+        .concat();
+        let cmap = [be16(0), be16(1), be16(3), be16(10), be32(12), subtable].concat();
+
+        let tables = [
+            (b"cmap", cmap),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"maxp", maxp),
+        ];
+        let mut data = [be32(0x0001_0000), be16(tables.len() as u16), vec![0; 6]].concat();
+        let mut offset = data.len() + 16 * tables.len();
+        for (tag, table) in &tables {
+            data.extend(tag.iter());
+            data.extend([be32(0), be32(offset as u32), be32(table.len() as u32)].concat());
+            offset += table.len();
+        }
+        for (_, table) in tables {
+            data.extend(table);
+        }
+
+        ab_glyph::FontArc::try_from_vec(data).unwrap()
+    }
+
+    fn chain() -> Vec<ab_glyph::FontArc> {
+        vec![font(&['a', 'b']), font(&['b', 'é', '日'])]
+    }
+
+    #[test]
+    fn covers_chain() {
+        let fonts = chain();
+        assert_eq!(font_for(&fonts, 'a'), Some(wgpu_glyph::FontId(0)));
+        assert_eq!(font_for(&fonts, 'b'), Some(wgpu_glyph::FontId(0)));
+        assert_eq!(font_for(&fonts, '日'), Some(wgpu_glyph::FontId(1)));
+        assert_eq!(font_for(&fonts, 'z'), None);
+
+        assert!(covers(&fonts, 'é'));
+        assert!(covers(&fonts, ' '));
+        assert!(covers(&fonts, '\n'));
+        assert!(!covers(&fonts, 'z'));
+    }
+
+    #[test]
+    fn missing_chars_unique_in_order() {
+        let fonts = chain();
+        assert_eq!(missing_chars(&fonts, "ab é日\n"), []);
+        assert_eq!(missing_chars(&fonts, "zaxz日x"), ['z', 'x']);
+    }
+
+    #[test]
+    fn runs_switch_fonts() {
+        let fonts = chain();
+        let runs = |text| {
+            runs(&fonts, text)
+                .into_iter()
+                .map(|(range, font)| (range, font.0))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(runs(""), []);
+        assert_eq!(runs("ab"), [(0..2, 0)]);
+        // 'é' and '日' are two and three bytes long
+        assert_eq!(runs("aé日b"), [(0..1, 0), (1..6, 1), (6..7, 0)]);
+        // whitespace and uncovered characters stay in the current run
+        assert_eq!(runs("é z\nb"), [(0..5, 1), (5..6, 0)]);
+        // or start the first run with the first font
+        assert_eq!(runs("z é"), [(0..2, 0), (2..4, 1)]);
+    }
+}
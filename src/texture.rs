@@ -11,11 +11,22 @@ use std::{
 
 static NEXT_TEXTURE_ID: AtomicU64 = AtomicU64::new(0);
 
+/// Properties of a [Texture] and its view, used to validate bindings against a [LayoutEntry].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureInfo {
+    pub format: wgpu::TextureFormat,
+    pub samples: u32,
+    /// Dimension of the texture view.
+    pub dimension: wgpu::TextureViewDimension,
+    pub usage: wgpu::TextureUsages,
+}
+
 /// Texture wrapper type storing a texture *and* texture view.
 #[derive(Debug, Clone)]
 pub struct Texture {
     pub texture: Arc<wgpu::Texture>,
     pub view: Arc<wgpu::TextureView>,
+    info: Option<TextureInfo>,
     id: u64,
 }
 
@@ -25,10 +36,29 @@ impl Texture {
         Texture {
             texture,
             view,
+            info: None,
             id: NEXT_TEXTURE_ID.fetch_add(1, SeqCst),
         }
     }
 
+    /// Creates a new [Texture] from an existing texture and texture view, described by `info`.
+    pub fn with_info(
+        texture: Arc<wgpu::Texture>,
+        view: Arc<wgpu::TextureView>,
+        info: TextureInfo,
+    ) -> Self {
+        Texture {
+            info: Some(info),
+            ..Texture::new(texture, view)
+        }
+    }
+
+    /// Returns the properties of the texture, if known.
+    #[inline]
+    pub fn info(&self) -> Option<&TextureInfo> {
+        self.info.as_ref()
+    }
+
     /// Returns an ID uniquely identifying this [Texture].
     #[inline]
     pub fn id(&self) -> u64 {
@@ -38,6 +68,8 @@ impl Texture {
 
 /// Texture descriptor for image texture; i.e. textures initialized with pixel data.
 pub struct ImageTexture<'a> {
+    /// Debug label of the texture.
+    pub label: Option<&'a str>,
     pub format: wgpu::TextureFormat,
     pub pixels: Cow<'a, [u8]>,
     pub width: u32,
@@ -51,6 +83,7 @@ impl<'a> ImageTexture<'a> {
         let (width, height) = (image.width(), image.height());
         let raw = image.into_raw();
         Ok(ImageTexture {
+            label: None,
             format: if srgb {
                 wgpu::TextureFormat::Rgba8UnormSrgb
             } else {
@@ -70,6 +103,7 @@ impl<'a> ImageTexture<'a> {
         let (width, height) = (image.width(), image.height());
         let raw = image.as_raw();
         ImageTexture {
+            label: None,
             format,
             pixels: Cow::Borrowed(raw),
             width,
@@ -77,10 +111,38 @@ impl<'a> ImageTexture<'a> {
         }
     }
 
+    /// Multiplies the color channels of every pixel by its alpha, for use with [BlendMode::PremultipliedAlpha].
+    ///
+    /// For sRGB formats this is done in linear space.
+    /// Only 8-bit RGBA and BGRA formats are supported; other formats are left unchanged.
+    pub fn premultiply(mut self) -> Self {
+        use wgpu::TextureFormat::*;
+        let srgb = match self.format {
+            Rgba8Unorm | Bgra8Unorm => false,
+            Rgba8UnormSrgb | Bgra8UnormSrgb => true,
+            _ => return self,
+        };
+
+        for pixel in self.pixels.to_mut().chunks_exact_mut(4) {
+            let alpha = pixel[3] as f32 / 255.;
+            for channel in &mut pixel[..3] {
+                let value = *channel as f32 / 255.;
+                let value = if srgb {
+                    linear_to_srgb(srgb_to_linear(value) * alpha)
+                } else {
+                    value * alpha
+                };
+                *channel = (value * 255.).round() as u8;
+            }
+        }
+        self
+    }
+
     /// Creates a new [Texture] from the stored image texture.
     pub fn create(self, cx: &Context) -> Texture {
+        let usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
         let texture = cx.device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
+            label: self.label,
             size: wgpu::Extent3d {
                 width: self.width,
                 height: self.height,
@@ -90,7 +152,7 @@ impl<'a> ImageTexture<'a> {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: self.format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            usage,
         });
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -112,12 +174,23 @@ impl<'a> ImageTexture<'a> {
             },
         );
 
-        Texture::new(Arc::new(texture), Arc::new(view))
+        Texture::with_info(
+            Arc::new(texture),
+            Arc::new(view),
+            TextureInfo {
+                format: self.format,
+                samples: 1,
+                dimension: wgpu::TextureViewDimension::D2,
+                usage,
+            },
+        )
     }
 }
 
 /// Texture descriptor for rendering use.
-pub struct RenderTexture {
+pub struct RenderTexture<'a> {
+    /// Debug label of the texture.
+    pub label: Option<&'a str>,
     pub format: wgpu::TextureFormat,
     pub samples: u32,
     pub width: u32,
@@ -128,9 +201,10 @@ pub struct RenderTexture {
     pub binding: bool,
 }
 
-impl RenderTexture {
-    pub fn from_depth(samples: u32, width: u32, height: u32, binding: bool) -> RenderTexture {
+impl<'a> RenderTexture<'a> {
+    pub fn from_depth(samples: u32, width: u32, height: u32, binding: bool) -> Self {
         RenderTexture {
+            label: None,
             format: wgpu::TextureFormat::Depth32Float,
             samples,
             width,
@@ -141,8 +215,19 @@ impl RenderTexture {
 
     /// Creates a new [Texture] for rendering use from the stored options.
     pub fn create(self, cx: &Context) -> Texture {
+        self.create_with_usage(cx, wgpu::TextureUsages::empty())
+    }
+
+    /// Same as [RenderTexture::create], but the texture also has `usage`, e.g. to copy from it.
+    pub fn create_with_usage(self, cx: &Context, usage: wgpu::TextureUsages) -> Texture {
+        let usage = usage
+            | if self.binding {
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+            } else {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+            };
         let texture = cx.device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
+            label: self.label,
             size: wgpu::Extent3d {
                 width: self.width,
                 height: self.height,
@@ -152,15 +237,20 @@ impl RenderTexture {
             sample_count: self.samples,
             dimension: wgpu::TextureDimension::D2,
             format: self.format,
-            usage: if self.binding {
-                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
-            } else {
-                wgpu::TextureUsages::RENDER_ATTACHMENT
-            },
+            usage,
         });
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-        Texture::new(Arc::new(texture), Arc::new(view))
+        Texture::with_info(
+            Arc::new(texture),
+            Arc::new(view),
+            TextureInfo {
+                format: self.format,
+                samples: self.samples,
+                dimension: wgpu::TextureViewDimension::D2,
+                usage,
+            },
+        )
     }
 }